@@ -0,0 +1,55 @@
+use crate::math::vector3d::Vector3D;
+use crate::core::camera::Camera;
+use crate::scene::scene::Scene;
+use crate::math::track::Track;
+
+// Keyed camera and light parameters, set on the scene before each frame. Objects are
+// animated through their nodes' motion instead, which also gives them motion blur.
+#[derive(Default)]
+pub struct Animation {
+    pub camera_position: Option<Track<Vector3D>>,
+    pub camera_look_at: Option<Track<Vector3D>>,
+    // Tracks for the intensity of `scene.lights[index]`
+    pub light_intensities: Vec<(usize, Track<f32>)>,
+}
+
+impl Animation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_camera_position(mut self, track: Track<Vector3D>) -> Self {
+        self.camera_position = Some(track);
+        self
+    }
+
+    pub fn with_camera_look_at(mut self, track: Track<Vector3D>) -> Self {
+        self.camera_look_at = Some(track);
+        self
+    }
+
+    pub fn with_light_intensity(mut self, light: usize, track: Track<f32>) -> Self {
+        self.light_intensities.push((light, track));
+        self
+    }
+
+    // Time of the last key of any track
+    pub fn end_time(&self) -> f32 {
+        self.camera_position.iter()
+            .chain(&self.camera_look_at)
+            .filter_map(|track| track.end_time())
+            .chain(self.light_intensities.iter().filter_map(|(_, track)| track.end_time()))
+            .fold(0.0, f32::max)
+    }
+
+    pub fn apply(&self, scene: &mut Scene, camera: &mut Camera, time: f32) {
+        let position = self.camera_position.as_ref().and_then(|track| track.value_at(time));
+        let look_at = self.camera_look_at.as_ref().and_then(|track| track.value_at(time));
+        camera.set_pose(position.unwrap_or(camera.position()), look_at.unwrap_or(camera.look_at()));
+        for (index, track) in &self.light_intensities {
+            if let (Some(light), Some(intensity)) = (scene.lights.get_mut(*index), track.value_at(time)) {
+                light.intensity = intensity;
+            }
+        }
+    }
+}
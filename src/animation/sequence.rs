@@ -0,0 +1,92 @@
+use std::fs;
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use image::ImageResult;
+use crate::core::camera::Camera;
+use crate::cameras::path::CameraPath;
+use crate::scene::scene::Scene;
+use crate::renderer::renderer::Renderer;
+use crate::renderer::output::save_png;
+use super::animation::Animation;
+
+// A range of frames rendered to numbered images in `directory`. Frame 1 is at time 0.
+pub struct Sequence {
+    pub first: u32,
+    pub last: u32,
+    pub frame_rate: f32,
+    // Fraction of a frame the shutter stays open (0.5 for a 180° shutter), 0 for no
+    // motion blur
+    pub shutter: f32,
+    pub directory: PathBuf,
+    // Skip frames whose image already exists
+    pub resume: bool,
+    // Camera move over the frames, frame 1 taking the path's first pose
+    pub camera_path: Option<CameraPath>,
+}
+
+impl Sequence {
+    pub fn new(first: u32, last: u32, frame_rate: f32, directory: PathBuf) -> Self {
+        Self { first, last, frame_rate: frame_rate.max(1e-3), shutter: 0.0, directory, resume: false, camera_path: None }
+    }
+
+    pub fn with_shutter(mut self, shutter: f32) -> Self {
+        self.shutter = shutter.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_resume(mut self, resume: bool) -> Self {
+        self.resume = resume;
+        self
+    }
+
+    pub fn with_camera_path(mut self, path: CameraPath) -> Self {
+        self.camera_path = Some(path);
+        self
+    }
+
+    pub fn frames(&self) -> RangeInclusive<u32> {
+        self.first..=self.last
+    }
+
+    pub fn frame_time(&self, frame: u32) -> f32 {
+        (frame as f32 - 1.0) / self.frame_rate
+    }
+
+    pub fn frame_path(&self, frame: u32) -> PathBuf {
+        self.directory.join(format!("frame_{:04}.png", frame))
+    }
+
+    // Images are moved into place once complete, so any file found is a finished frame
+    pub fn is_rendered(&self, frame: u32) -> bool {
+        self.frame_path(frame).is_file()
+    }
+
+    pub fn needs_render(&self, frame: u32) -> bool {
+        !(self.resume && self.is_rendered(frame))
+    }
+
+    // Poses the scene and camera for the frame, renders it and saves the image
+    pub fn render_frame(
+        &self,
+        frame: u32,
+        renderer: &Renderer,
+        scene: &mut Scene,
+        camera: &mut Camera,
+        animation: &Animation,
+    ) -> ImageResult<PathBuf> {
+        let time = self.frame_time(frame);
+        animation.apply(scene, camera, time);
+        if let Some(path) = &self.camera_path {
+            path.apply(camera, frame.saturating_sub(1));
+        }
+        camera.shutter_open = time;
+        camera.shutter_close = time + self.shutter / self.frame_rate;
+
+        let image = renderer.render(scene, camera);
+        fs::create_dir_all(&self.directory)?;
+        let path = self.frame_path(frame);
+        let region = renderer.region();
+        save_png(&path, &image, region.width, region.height)?;
+        Ok(path)
+    }
+}
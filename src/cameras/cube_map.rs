@@ -0,0 +1,57 @@
+use crate::math::vector3d::Vector3D;
+use crate::core::ray::Ray;
+use crate::core::camera::{Projection, View};
+use super::panoramic::stereo_origin;
+
+// Six 90° faces side by side, in the order right, left, up, down, front, back relative
+// to the view (a 6:1 image). A stereo cube map stacks the left eye above the right eye
+// (6:2), each eye offset by half the interpupillary distance.
+pub struct CubeMap {
+    pub view: View,
+    // Interpupillary distance for stereo, None for a single viewpoint
+    pub stereo: Option<f32>,
+}
+
+impl CubeMap {
+    pub fn new(position: Vector3D, look_at: Vector3D, up: Vector3D) -> Self {
+        Self { view: View::new(position, look_at, up), stereo: None }
+    }
+
+    pub fn with_stereo(mut self, interpupillary_distance: f32) -> Self {
+        self.stereo = Some(interpupillary_distance);
+        self
+    }
+}
+
+impl Projection for CubeMap {
+    fn generate_lens_ray(&self, s: f32, t: f32, _lens: (f32, f32)) -> Option<Ray> {
+        let (t, eye_offset) = match self.stereo {
+            Some(ipd) if t >= 0.5 => (2.0 * t - 1.0, -0.5 * ipd),
+            Some(ipd) => (2.0 * t, 0.5 * ipd),
+            None => (t, 0.0),
+        };
+
+        let scaled = s.clamp(0.0, 1.0) * 6.0;
+        let face = (scaled.floor() as usize).min(5);
+        let a = 2.0 * (scaled - face as f32) - 1.0;
+        let b = 2.0 * t - 1.0;
+
+        let (u, v, w) = self.view.basis();
+        let (forward, face_up) = match face {
+            0 => (u, v),
+            1 => (-u, v),
+            2 => (v, w),
+            3 => (-v, -w),
+            4 => (-w, v),
+            _ => (w, v),
+        };
+        let direction = forward + forward.cross(&face_up) * a + face_up * b;
+
+        let origin = if eye_offset != 0.0 {
+            stereo_origin(&self.view.position(), &v, &direction.normalize(), eye_offset)
+        } else {
+            self.view.position()
+        };
+        Some(Ray::new(origin, direction))
+    }
+}
@@ -0,0 +1,40 @@
+use std::f32::consts::PI;
+use crate::math::vector3d::Vector3D;
+use crate::core::ray::Ray;
+use crate::core::camera::{Projection, View};
+
+// Equidistant fisheye: the angle from the view axis grows linearly with the distance
+// from the image centre, reaching fov / 2 on the image circle inscribed in the frame.
+// Fields of view up to 360° are allowed; pixels outside the circle stay empty.
+pub struct Fisheye {
+    pub view: View,
+    pub fov: f32,
+    pub aspect_ratio: f32,
+}
+
+impl Fisheye {
+    pub fn new(position: Vector3D, look_at: Vector3D, up: Vector3D, fov: f32, aspect_ratio: f32) -> Self {
+        Self { view: View::new(position, look_at, up), fov, aspect_ratio }
+    }
+}
+
+impl Projection for Fisheye {
+    fn generate_lens_ray(&self, s: f32, t: f32, _lens: (f32, f32)) -> Option<Ray> {
+        let (mut x, mut y) = (2.0 * s - 1.0, 2.0 * t - 1.0);
+        if self.aspect_ratio >= 1.0 {
+            x *= self.aspect_ratio;
+        } else {
+            y /= self.aspect_ratio;
+        }
+        let radius = (x * x + y * y).sqrt();
+        if radius > 1.0 {
+            return None;
+        }
+
+        let theta = radius * self.fov.min(360.0) * PI / 360.0;
+        let phi = y.atan2(x);
+        let (u, v, w) = self.view.basis();
+        let direction = (u * phi.cos() + v * phi.sin()) * theta.sin() - w * theta.cos();
+        Some(Ray::new(self.view.position(), direction))
+    }
+}
@@ -0,0 +1,6 @@
+pub mod orthographic;
+pub mod fisheye;
+pub mod panoramic;
+pub mod cube_map;
+pub mod stereo;
+pub mod path;
@@ -0,0 +1,26 @@
+use crate::math::vector3d::Vector3D;
+use crate::core::ray::Ray;
+use crate::core::camera::{Projection, View};
+
+// Parallel projection for technical drawings: every ray runs along the view direction
+// from a `height` by `height * aspect_ratio` window centred on the camera position.
+pub struct Orthographic {
+    pub view: View,
+    pub height: f32,
+    pub aspect_ratio: f32,
+}
+
+impl Orthographic {
+    pub fn new(position: Vector3D, look_at: Vector3D, up: Vector3D, height: f32, aspect_ratio: f32) -> Self {
+        Self { view: View::new(position, look_at, up), height, aspect_ratio }
+    }
+}
+
+impl Projection for Orthographic {
+    fn generate_lens_ray(&self, s: f32, t: f32, _lens: (f32, f32)) -> Option<Ray> {
+        let (u, v, w) = self.view.basis();
+        let width = self.height * self.aspect_ratio;
+        let origin = self.view.position() + u * ((s - 0.5) * width) + v * ((t - 0.5) * self.height);
+        Some(Ray::new(origin, -w))
+    }
+}
@@ -0,0 +1,80 @@
+use std::f32::consts::PI;
+use crate::math::vector3d::Vector3D;
+use crate::core::ray::Ray;
+use crate::core::camera::{Projection, View};
+
+// Full 360° by 180° latitude-longitude panorama, as used for VR previews and
+// environment maps. The image centre looks at `look_at`; use a 2:1 image. An
+// omni-directional stereo panorama stacks the left eye above the right eye (1:1).
+pub struct Equirectangular {
+    pub view: View,
+    // Interpupillary distance for stereo, None for a single viewpoint
+    pub stereo: Option<f32>,
+}
+
+impl Equirectangular {
+    pub fn new(position: Vector3D, look_at: Vector3D, up: Vector3D) -> Self {
+        Self { view: View::new(position, look_at, up), stereo: None }
+    }
+
+    pub fn with_stereo(mut self, interpupillary_distance: f32) -> Self {
+        self.stereo = Some(interpupillary_distance);
+        self
+    }
+
+    // Direction for the image point (s, t) in the camera frame
+    pub fn direction(&self, s: f32, t: f32) -> Vector3D {
+        let longitude = (s - 0.5) * 2.0 * PI;
+        let latitude = (t - 0.5) * PI;
+        let (u, v, w) = self.view.basis();
+        (u * longitude.sin() - w * longitude.cos()) * latitude.cos() + v * latitude.sin()
+    }
+}
+
+impl Projection for Equirectangular {
+    fn generate_lens_ray(&self, s: f32, t: f32, _lens: (f32, f32)) -> Option<Ray> {
+        let (t, eye_offset) = match self.stereo {
+            Some(ipd) if t >= 0.5 => (2.0 * t - 1.0, -0.5 * ipd),
+            Some(ipd) => (2.0 * t, 0.5 * ipd),
+            None => return Some(Ray::new(self.view.position(), self.direction(s, t))),
+        };
+        let direction = self.direction(s, t);
+        let (_, v, _) = self.view.basis();
+        let origin = stereo_origin(&self.view.position(), &v, &direction, eye_offset);
+        Some(Ray::new(origin, direction))
+    }
+}
+
+// Cylindrical panorama: angles spread evenly across `horizontal_fov` (up to 360°) while
+// vertical lines stay straight, as a perspective camera would show them within
+// `vertical_fov`.
+pub struct Cylindrical {
+    pub view: View,
+    pub horizontal_fov: f32,
+    pub vertical_fov: f32,
+}
+
+impl Cylindrical {
+    pub fn new(position: Vector3D, look_at: Vector3D, up: Vector3D, horizontal_fov: f32, vertical_fov: f32) -> Self {
+        Self { view: View::new(position, look_at, up), horizontal_fov, vertical_fov }
+    }
+}
+
+impl Projection for Cylindrical {
+    fn generate_lens_ray(&self, s: f32, t: f32, _lens: (f32, f32)) -> Option<Ray> {
+        let angle = (s - 0.5) * self.horizontal_fov.min(360.0) * PI / 180.0;
+        let height = (2.0 * t - 1.0) * (self.vertical_fov * PI / 360.0).tan();
+        let (u, v, w) = self.view.basis();
+        let direction = u * angle.sin() - w * angle.cos() + v * height;
+        Some(Ray::new(self.view.position(), direction))
+    }
+}
+
+// Omni-directional stereo: an eye's viewpoint for looking along `direction`, on a circle
+// of radius |eye_offset| around `position` (negative for the left eye). The offset
+// shrinks towards the poles so the two eyes meet straight up and down.
+pub fn stereo_origin(position: &Vector3D, up: &Vector3D, direction: &Vector3D, eye_offset: f32) -> Vector3D {
+    let up = up.normalize();
+    let horizontal = *direction - up * direction.dot(&up);
+    *position + horizontal.cross(&up) * eye_offset
+}
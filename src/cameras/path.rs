@@ -0,0 +1,121 @@
+use crate::math::vector3d::Vector3D;
+use crate::math::matrix4::Matrix4;
+use crate::core::camera::Camera;
+
+// Where a camera stands and what it looks at
+#[derive(Debug, Copy, Clone)]
+pub struct CameraPose {
+    pub position: Vector3D,
+    pub look_at: Vector3D,
+}
+
+// What a camera following a spline looks at
+#[derive(Debug, Clone)]
+pub enum Target {
+    Fixed(Vector3D),
+    // A target moving along its own spline, in step with the camera
+    Spline(Vec<Vector3D>),
+    // Straight ahead along the path
+    Forward,
+}
+
+// Camera moves laid out over a number of frames, frame 0 being the first
+#[derive(Debug, Clone)]
+pub enum CameraPath {
+    // One full turn around `target` about the `axis` through it, starting from `start`.
+    // The last frame stops one step short of the start so the turntable loops.
+    Orbit { target: Vector3D, start: Vector3D, axis: Vector3D, frames: u32 },
+    // Straight move from `from` to `to`, keeping the view direction
+    Dolly { from: Vector3D, to: Vector3D, direction: Vector3D, frames: u32 },
+    // Catmull-Rom spline through the waypoints
+    Spline { waypoints: Vec<Vector3D>, target: Target, frames: u32 },
+}
+
+impl CameraPath {
+    // Turntable around what the camera looks at, about its up direction
+    pub fn orbit(camera: &Camera, frames: u32) -> Self {
+        CameraPath::Orbit { target: camera.look_at(), start: camera.position(), axis: camera.up(), frames }
+    }
+
+    pub fn dolly(camera: &Camera, to: Vector3D, frames: u32) -> Self {
+        CameraPath::Dolly { from: camera.position(), to, direction: camera.look_at() - camera.position(), frames }
+    }
+
+    pub fn spline(waypoints: Vec<Vector3D>, target: Target, frames: u32) -> Self {
+        CameraPath::Spline { waypoints, target, frames }
+    }
+
+    pub fn frames(&self) -> u32 {
+        match self {
+            CameraPath::Orbit { frames, .. } | CameraPath::Dolly { frames, .. } | CameraPath::Spline { frames, .. } => *frames,
+        }
+    }
+
+    // Fraction of the way along the path at `frame`; open paths reach the end on the
+    // last frame
+    fn progress(&self, frame: u32) -> f32 {
+        let frames = self.frames().max(1);
+        match self {
+            CameraPath::Orbit { .. } => frame as f32 / frames as f32,
+            _ if frames == 1 => 0.0,
+            _ => (frame.min(frames - 1)) as f32 / (frames - 1) as f32,
+        }
+    }
+
+    pub fn pose(&self, frame: u32) -> CameraPose {
+        let t = self.progress(frame);
+        match self {
+            CameraPath::Orbit { target, start, axis, .. } => {
+                let rotation = Matrix4::rotation(*axis, 360.0 * t);
+                CameraPose { position: *target + rotation.transform_vector(&(*start - *target)), look_at: *target }
+            }
+            CameraPath::Dolly { from, to, direction, .. } => {
+                let position = *from + (*to - *from) * t;
+                CameraPose { position, look_at: position + *direction }
+            }
+            CameraPath::Spline { waypoints, target, .. } => {
+                let position = catmull_rom(waypoints, t);
+                let look_at = match target {
+                    Target::Fixed(point) => *point,
+                    Target::Spline(points) => catmull_rom(points, t),
+                    Target::Forward => {
+                        let step = 1e-3;
+                        let (a, b) = (catmull_rom(waypoints, (t - step).max(0.0)), catmull_rom(waypoints, (t + step).min(1.0)));
+                        position + (b - a).normalize()
+                    }
+                };
+                CameraPose { position, look_at }
+            }
+        }
+    }
+
+    // Places the camera for `frame`
+    pub fn apply(&self, camera: &mut Camera, frame: u32) {
+        let pose = self.pose(frame);
+        camera.set_pose(pose.position, pose.look_at);
+    }
+}
+
+// Point `t` in [0, 1] along the uniform Catmull-Rom spline through `points`, each segment
+// taking an equal share of `t`. The end points are repeated to close off the curve.
+pub fn catmull_rom(points: &[Vector3D], t: f32) -> Vector3D {
+    match points.len() {
+        0 => return Vector3D::new(0.0, 0.0, 0.0),
+        1 => return points[0],
+        _ => {}
+    }
+    let segments = points.len() - 1;
+    let scaled = t.clamp(0.0, 1.0) * segments as f32;
+    let i = (scaled.floor() as usize).min(segments - 1);
+    let s = scaled - i as f32;
+
+    let p0 = points[i.saturating_sub(1)];
+    let (p1, p2) = (points[i], points[i + 1]);
+    let p3 = points[(i + 2).min(segments)];
+    let (s2, s3) = (s * s, s * s * s);
+    (p1 * 2.0
+        + (p2 - p0) * s
+        + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * s2
+        + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * s3)
+        * 0.5
+}
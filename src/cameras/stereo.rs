@@ -0,0 +1,123 @@
+use std::f32::consts::PI;
+use crate::core::color::Color;
+use crate::core::ray::Ray;
+use crate::core::camera::{Camera, Projection};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Eye {
+    Left,
+    Right,
+}
+
+// How the two views are combined into one image
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StereoLayout {
+    // Left eye on the left half, right eye on the right half
+    SideBySide,
+    // Left eye on top of the right eye
+    OverUnder,
+    // Red channel from the left eye, green and blue from the right, for red/cyan glasses
+    Anaglyph,
+}
+
+impl StereoLayout {
+    // Size of the combined image for views of `width` by `height` pixels per eye
+    pub fn image_size(&self, width: u32, height: u32) -> (u32, u32) {
+        match self {
+            StereoLayout::SideBySide => (2 * width, height),
+            StereoLayout::OverUnder => (width, 2 * height),
+            StereoLayout::Anaglyph => (width, height),
+        }
+    }
+
+    // Combines two views of `width` by `height` pixels, stored bottom row first
+    pub fn combine(&self, left: &[Color], right: &[Color], width: u32, height: u32) -> Vec<Color> {
+        match self {
+            StereoLayout::SideBySide => (0..height as usize)
+                .flat_map(|y| {
+                    let row = y * width as usize..(y + 1) * width as usize;
+                    left[row.clone()].iter().chain(&right[row]).copied()
+                })
+                .collect(),
+            StereoLayout::OverUnder => right.iter().chain(left).copied().collect(),
+            StereoLayout::Anaglyph => left
+                .iter()
+                .zip(right)
+                .map(|(l, r)| Color::new(l.r, r.g, r.b))
+                .collect(),
+        }
+    }
+}
+
+// Two perspective cameras `interpupillary_distance` apart either side of `camera`, looking
+// the same way. With a convergence distance the eyes' frusta are sheared (off-axis) so
+// objects at that distance line up in both views and appear on the screen plane; nearer
+// ones pop out of it. Parallel eyes put the screen plane at infinity.
+pub struct StereoRig {
+    pub camera: Camera,
+    pub interpupillary_distance: f32,
+    pub convergence: Option<f32>,
+}
+
+impl StereoRig {
+    pub fn new(camera: Camera, interpupillary_distance: f32) -> Self {
+        Self { camera, interpupillary_distance, convergence: None }
+    }
+
+    pub fn with_convergence(mut self, distance: f32) -> Self {
+        self.convergence = Some(distance.max(1e-3));
+        self
+    }
+
+    pub fn with_parallel_eyes(mut self) -> Self {
+        self.convergence = None;
+        self
+    }
+
+    pub fn eye(&self, eye: Eye) -> EyeCamera<'_> {
+        EyeCamera { rig: self, eye }
+    }
+}
+
+// One eye of a stereo rig
+pub struct EyeCamera<'a> {
+    pub rig: &'a StereoRig,
+    pub eye: Eye,
+}
+
+impl EyeCamera<'_> {
+    // Signed distance from the rig's centre along the camera's right axis
+    pub fn offset(&self) -> f32 {
+        let half = 0.5 * self.rig.interpupillary_distance;
+        match self.eye {
+            Eye::Left => -half,
+            Eye::Right => half,
+        }
+    }
+}
+
+impl Projection for EyeCamera<'_> {
+    fn generate_lens_ray(&self, s: f32, t: f32, lens: (f32, f32)) -> Option<Ray> {
+        let camera = &self.rig.camera;
+        let (u, _, _) = camera.basis();
+        let offset = self.offset();
+
+        // Shifting the image window by the eye's offset, scaled to the convergence plane,
+        // points both eyes at the same spot there
+        let shift = match self.rig.convergence {
+            Some(distance) => {
+                let width = 2.0 * camera.aspect_ratio() * (camera.fov() * PI / 360.0).tan();
+                -offset / (distance * width)
+            }
+            None => 0.0,
+        };
+        // The eye sees what the rig's camera would from one side
+        let mut ray = camera.generate_lens_ray(s + shift, t, lens)?;
+        ray.origin = ray.origin + u * offset;
+        Some(ray)
+    }
+
+    fn shutter(&self) -> (f32, f32) {
+        self.rig.camera.shutter()
+    }
+}
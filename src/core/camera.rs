@@ -1,42 +1,424 @@
 use crate::math::vector3d::Vector3D;
-use crate::core::ray::Ray;
+use crate::math::matrix4::Matrix4;
+use crate::core::ray::{Ray, RayDifferential};
 use std::f32::consts::PI;
 
+// Full-frame 35mm sensor, in millimetres
+const SENSOR_WIDTH_MM: f32 = 36.0;
+const SENSOR_HEIGHT_MM: f32 = 24.0;
+// Scene units are metres for lens sizes derived from millimetres
+const MM_PER_UNIT: f32 = 1000.0;
+
+// Maps image coordinates to camera rays. (s, t) run over [0, 1]² from left to right and
+// bottom to top; `lens` in [0, 1)² picks a point on the aperture, (0.5, 0.5) being its
+// centre. Projections that don't cover the whole image return None outside it.
+pub trait Projection: Send + Sync {
+    fn generate_lens_ray(&self, s: f32, t: f32, lens: (f32, f32)) -> Option<Ray>;
+
+    fn generate_ray(&self, s: f32, t: f32) -> Option<Ray> {
+        self.generate_lens_ray(s, t, (0.5, 0.5))
+    }
+
+    // Interval of scene time the exposure covers; rays are spread over it for motion blur
+    fn shutter(&self) -> (f32, f32) {
+        (0.0, 0.0)
+    }
+
+    // Ray through (s, t) carrying differentials towards (s + ds, t) and (s, t + dt), all
+    // through the same point on the lens
+    fn generate_ray_differential(&self, s: f32, t: f32, ds: f32, dt: f32, lens: (f32, f32)) -> Option<Ray> {
+        let ray = self.generate_lens_ray(s, t, lens)?;
+        match (self.generate_lens_ray(s + ds, t, lens), self.generate_lens_ray(s, t + dt, lens)) {
+            (Some(rx), Some(ry)) => Some(ray.with_differential(RayDifferential {
+                rx_origin: rx.origin,
+                rx_direction: rx.direction,
+                ry_origin: ry.origin,
+                ry_direction: ry.direction,
+            })),
+            _ => Some(ray),
+        }
+    }
+}
+
+// Camera frame looking from `position` towards `look_at`: right, up, and backwards.
+// When `up` is (nearly) parallel to the view, the world axis least aligned with it takes
+// its place; with `position` on `look_at` the camera looks down +z.
+pub fn look_at_basis(position: &Vector3D, look_at: &Vector3D, up: &Vector3D) -> (Vector3D, Vector3D, Vector3D) {
+    let view = *position - *look_at;
+    let w = if view.length() > 1e-8 { view.normalize() } else { Vector3D::new(0.0, 0.0, -1.0) };
+    let mut u = up.cross(&w);
+    if u.length() <= 1e-6 * up.length() {
+        let (x, y, z) = (w.x.abs(), w.y.abs(), w.z.abs());
+        let fallback = if y <= x && y <= z {
+            Vector3D::new(0.0, 1.0, 0.0)
+        } else if z <= x {
+            Vector3D::new(0.0, 0.0, 1.0)
+        } else {
+            Vector3D::new(1.0, 0.0, 0.0)
+        };
+        u = fallback.cross(&w);
+    }
+    let u = u.normalize();
+    let v = w.cross(&u);
+    (u, v, w)
+}
+
+// Where a projection stands and looks, with the frame from `look_at_basis` worked out
+// once. The pose only changes through `new` and `set_pose`, so the frame can't go stale.
+#[derive(Debug, Copy, Clone)]
+pub struct View {
+    position: Vector3D,
+    look_at: Vector3D,
+    up: Vector3D,
+    u: Vector3D,
+    v: Vector3D,
+    w: Vector3D,
+}
+
+impl View {
+    pub fn new(position: Vector3D, look_at: Vector3D, up: Vector3D) -> Self {
+        let (u, v, w) = look_at_basis(&position, &look_at, &up);
+        Self { position, look_at, up, u, v, w }
+    }
+
+    pub fn position(&self) -> Vector3D {
+        self.position
+    }
+
+    pub fn look_at(&self) -> Vector3D {
+        self.look_at
+    }
+
+    pub fn up(&self) -> Vector3D {
+        self.up
+    }
+
+    // Right, up and backwards
+    pub fn basis(&self) -> (Vector3D, Vector3D, Vector3D) {
+        (self.u, self.v, self.w)
+    }
+
+    pub fn set_pose(&mut self, position: Vector3D, look_at: Vector3D) {
+        *self = Self::new(position, look_at, self.up);
+    }
+}
+
+// Everything about the view that doesn't change from ray to ray, worked out once
+#[derive(Debug, Copy, Clone)]
+struct Basis {
+    u: Vector3D,
+    v: Vector3D,
+    w: Vector3D,
+    // The image's lower left corner one unit in front of the lens, and its two edges
+    lower_left_corner: Vector3D,
+    horizontal: Vector3D,
+    vertical: Vector3D,
+    // Normal of the plane in focus, tilted with the lens
+    focal_normal: Vector3D,
+}
+
+// Perspective camera with a vertical field of view in degrees. The view is precomputed,
+// so whatever shapes it is private and changes through the `with_*` and `set_*` methods.
+#[derive(Debug, Clone)]
 pub struct Camera {
-    pub position: Vector3D,
-    pub look_at: Vector3D,
-    pub up: Vector3D,
-    pub fov: f32,
-    pub aspect_ratio: f32,
+    position: Vector3D,
+    look_at: Vector3D,
+    up: Vector3D,
+    fov: f32,
+    aspect_ratio: f32,
+    // Lens radius in scene units; 0 keeps the camera a pinhole with everything sharp
+    pub aperture: f32,
+    // Distance to the plane in focus, along the view direction. None focuses on `look_at`.
+    pub focus_distance: Option<f32>,
+    // Diaphragm blades shaping the bokeh; fewer than 3 gives a round aperture
+    pub blades: u32,
+    // Rotation of the polygonal aperture, in degrees
+    pub blade_rotation: f32,
+    // Scene times the shutter opens and closes at; equal times freeze motion
+    pub shutter_open: f32,
+    pub shutter_close: f32,
+    // Sensor size in millimetres, 36 by 24 by default
+    sensor_width: f32,
+    sensor_height: f32,
+    // Lens shift across the sensor in millimetres, right and up, moving the image without
+    // converging verticals
+    shift: (f32, f32),
+    // Lens tilt in degrees about the horizontal and vertical axes, tilting the plane in
+    // focus (Scheimpflug)
+    tilt: (f32, f32),
+    basis: Basis,
 }
 
 impl Camera {
     pub fn new(position: Vector3D, look_at: Vector3D, up: Vector3D, fov: f32, aspect_ratio: f32) -> Self {
-        Self {
+        let mut camera = Self {
             position,
             look_at,
             up,
             fov,
             aspect_ratio,
-        }
+            aperture: 0.0,
+            focus_distance: None,
+            blades: 0,
+            blade_rotation: 0.0,
+            shutter_open: 0.0,
+            shutter_close: 0.0,
+            sensor_width: SENSOR_WIDTH_MM,
+            sensor_height: SENSOR_HEIGHT_MM,
+            shift: (0.0, 0.0),
+            tilt: (0.0, 0.0),
+            basis: Basis {
+                u: Vector3D::new(1.0, 0.0, 0.0),
+                v: Vector3D::new(0.0, 1.0, 0.0),
+                w: Vector3D::new(0.0, 0.0, 1.0),
+                lower_left_corner: Vector3D::new(0.0, 0.0, -1.0),
+                horizontal: Vector3D::new(0.0, 0.0, 0.0),
+                vertical: Vector3D::new(0.0, 0.0, 0.0),
+                focal_normal: Vector3D::new(0.0, 0.0, 1.0),
+            },
+        };
+        camera.update();
+        camera
     }
 
-    pub fn generate_ray(&self, s: f32, t: f32) -> Ray {
+    fn update(&mut self) {
         let theta = self.fov * PI / 180.0;
         let half_height = (theta / 2.0).tan();
         let half_width = self.aspect_ratio * half_height;
+        let (u, v, w) = look_at_basis(&self.position, &self.look_at, &self.up);
+
+        // A shift of d mm on the sensor moves the image by d / f one unit in front of the lens
+        let focal_length = self.focal_length();
+        let shift = u * (self.shift.0 / focal_length) + v * (self.shift.1 / focal_length);
+        let lower_left_corner = self.position + shift - u * half_width - v * half_height - w;
+
+        let tilted = Matrix4::rotation(u, self.tilt.0) * Matrix4::rotation(v, self.tilt.1);
+        self.basis = Basis {
+            u,
+            v,
+            w,
+            lower_left_corner,
+            horizontal: u * 2.0 * half_width,
+            vertical: v * 2.0 * half_height,
+            focal_normal: tilted.transform_vector(&w).normalize(),
+        };
+    }
+
+    pub fn position(&self) -> Vector3D {
+        self.position
+    }
+
+    pub fn look_at(&self) -> Vector3D {
+        self.look_at
+    }
+
+    pub fn up(&self) -> Vector3D {
+        self.up
+    }
+
+    pub fn fov(&self) -> f32 {
+        self.fov
+    }
+
+    pub fn aspect_ratio(&self) -> f32 {
+        self.aspect_ratio
+    }
+
+    // Sensor width and height in millimetres
+    pub fn sensor(&self) -> (f32, f32) {
+        (self.sensor_width, self.sensor_height)
+    }
+
+    pub fn shift(&self) -> (f32, f32) {
+        self.shift
+    }
+
+    pub fn tilt(&self) -> (f32, f32) {
+        self.tilt
+    }
+
+    // Right, up and backwards, as `look_at_basis` gives them
+    pub fn basis(&self) -> (Vector3D, Vector3D, Vector3D) {
+        (self.basis.u, self.basis.v, self.basis.w)
+    }
+
+    pub fn set_pose(&mut self, position: Vector3D, look_at: Vector3D) {
+        self.position = position;
+        self.look_at = look_at;
+        self.update();
+    }
+
+    pub fn with_aspect_ratio(mut self, aspect_ratio: f32) -> Self {
+        self.aspect_ratio = aspect_ratio;
+        self.update();
+        self
+    }
+
+    // Focal length in millimetres giving the field of view on the sensor's height
+    pub fn focal_length(&self) -> f32 {
+        0.5 * self.sensor_height / (self.fov * PI / 360.0).tan()
+    }
+
+    // Sets the field of view from a lens focal length in millimetres
+    pub fn with_focal_length(mut self, focal_length: f32) -> Self {
+        self.fov = 2.0 * (0.5 * self.sensor_height / focal_length.max(1e-3)).atan() * 180.0 / PI;
+        self.update();
+        self
+    }
+
+    // Sensor size in millimetres; the field of view stays, so the focal length follows
+    pub fn with_sensor(mut self, width: f32, height: f32) -> Self {
+        self.sensor_width = width.max(1e-3);
+        self.sensor_height = height.max(1e-3);
+        self.update();
+        self
+    }
+
+    pub fn with_shift(mut self, x: f32, y: f32) -> Self {
+        self.shift = (x, y);
+        self.update();
+        self
+    }
 
-        let w = (self.position - self.look_at).normalize();
-        let u = self.up.cross(&w).normalize();
-        let v = w.cross(&u);
+    pub fn with_tilt(mut self, x: f32, y: f32) -> Self {
+        self.tilt = (x, y);
+        self.update();
+        self
+    }
+
+    pub fn with_aperture(mut self, radius: f32) -> Self {
+        self.aperture = radius.max(0.0);
+        self
+    }
 
-        let lower_left_corner = self.position - u * half_width - v * half_height - w;
-        let horizontal = u * 2.0 * half_width;
-        let vertical = v * 2.0 * half_height;
+    // Aperture from an f-number and the lens focal length, for scenes modelled in metres
+    pub fn with_f_stop(self, f_stop: f32) -> Self {
+        let radius = 0.5 * self.focal_length() / f_stop.max(1e-3) / MM_PER_UNIT;
+        self.with_aperture(radius)
+    }
+    pub fn with_focus_distance(mut self, distance: f32) -> Self {
+        self.focus_distance = Some(distance.max(1e-3));
+        self
+    }
+
+    pub fn with_autofocus(mut self) -> Self {
+        self.focus_distance = None;
+        self
+    }
 
-        Ray::new(
-            self.position,
-            lower_left_corner + horizontal * s + vertical * t - self.position,
-        )
+    pub fn with_blades(mut self, blades: u32, rotation: f32) -> Self {
+        self.blades = blades;
+        self.blade_rotation = rotation;
+        self
     }
-}
\ No newline at end of file
+
+    pub fn with_shutter(mut self, open: f32, close: f32) -> Self {
+        self.shutter_open = open;
+        self.shutter_close = close.max(open);
+        self
+    }
+
+    pub fn focus_distance(&self) -> f32 {
+        self.focus_distance.unwrap_or_else(|| (self.look_at - self.position).length())
+    }
+
+    // Point on the unit aperture for a sample in [0, 1)²; (0.5, 0.5) is the centre
+    pub fn sample_aperture(&self, u1: f32, u2: f32) -> (f32, f32) {
+        let (x, y) = concentric_disk(u1, u2);
+        if self.blades < 3 || (x == 0.0 && y == 0.0) {
+            return (x, y);
+        }
+
+        // Stretch the disk onto the polygon along each direction. Within a blade the
+        // angle is warped so that equal slices of disk angle cover equal areas of the
+        // blade's triangle, keeping the sampling uniform.
+        let radius = x.hypot(y);
+        let step = 2.0 * PI / self.blades as f32;
+        let half = step / 2.0;
+        let rotation = self.blade_rotation * PI / 180.0;
+        let angle = (y.atan2(x) - rotation).rem_euclid(2.0 * PI);
+        let blade = (angle / step).floor().min(self.blades as f32 - 1.0);
+        let fraction = angle / step - blade;
+        let within = half + ((2.0 * fraction - 1.0) * half.tan()).atan();
+        // Distance from the centre to the blade's edge in that direction
+        let edge = half.cos() / (within - half).cos();
+        let angle = blade * step + within + rotation;
+        (radius * edge * angle.cos(), radius * edge * angle.sin())
+    }
+}
+
+impl Projection for Camera {
+    fn generate_lens_ray(&self, s: f32, t: f32, lens: (f32, f32)) -> Option<Ray> {
+        let basis = &self.basis;
+        let direction = basis.lower_left_corner + basis.horizontal * s + basis.vertical * t - self.position;
+
+        if self.aperture <= 0.0 {
+            return Some(Ray::new(self.position, direction));
+        }
+
+        // Thin lens: rays from anywhere on the aperture meet the pinhole ray on the focal
+        // plane, which lies `focus_distance` along the view axis, tilted with the lens
+        let centre = basis.w * -self.focus_distance();
+        let depth = centre.dot(&basis.focal_normal) / direction.dot(&basis.focal_normal);
+        if depth <= 0.0 || !depth.is_finite() {
+            return None;
+        }
+        let focus = self.position + direction * depth;
+        let (lens_x, lens_y) = self.sample_aperture(lens.0, lens.1);
+        let origin = self.position + basis.u * (lens_x * self.aperture) + basis.v * (lens_y * self.aperture);
+        Some(Ray::new(origin, focus - origin))
+    }
+
+    fn shutter(&self) -> (f32, f32) {
+        (self.shutter_open, self.shutter_close)
+    }
+}
+
+// Shirley–Chiu mapping of the unit square onto the unit disk, keeping strata compact
+fn concentric_disk(u1: f32, u2: f32) -> (f32, f32) {
+    let (a, b) = (2.0 * u1 - 1.0, 2.0 * u2 - 1.0);
+    if a == 0.0 && b == 0.0 {
+        return (0.0, 0.0);
+    }
+    let (radius, angle) = if a.abs() > b.abs() {
+        (a, PI / 4.0 * (b / a))
+    } else {
+        (b, PI / 2.0 - PI / 4.0 * (a / b))
+    };
+    (radius * angle.cos(), radius * angle.sin())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn camera() -> Camera {
+        Camera::new(Vector3D::new(0.0, 0.0, -5.0), Vector3D::new(0.0, 0.0, 0.0), Vector3D::new(0.0, 1.0, 0.0), 60.0, 4.0 / 3.0)
+    }
+
+    #[test]
+    fn aperture_centre_sample_is_the_lens_centre() {
+        assert_eq!(camera().sample_aperture(0.5, 0.5), (0.0, 0.0));
+        for blades in 3..=9 {
+            for rotation in [0.0, 17.0, 90.0] {
+                assert_eq!(camera().with_blades(blades, rotation).sample_aperture(0.5, 0.5), (0.0, 0.0));
+            }
+        }
+    }
+
+    #[test]
+    fn bladed_aperture_samples_stay_inside_the_polygon() {
+        let camera = camera().with_blades(6, 0.0);
+        let apothem = (PI / 6.0).cos();
+        for i in 0..32 {
+            for j in 0..32 {
+                let (x, y) = camera.sample_aperture((i as f32 + 0.5) / 32.0, (j as f32 + 0.5) / 32.0);
+                // Inside every edge of the hexagon
+                for edge in 0..6 {
+                    let angle = (edge as f32 + 0.5) * PI / 3.0;
+                    assert!(x * angle.cos() + y * angle.sin() <= apothem + 1e-5);
+                }
+            }
+        }
+    }
+}
@@ -11,6 +11,14 @@ impl Color {
     pub fn new(r: f32, g: f32, b: f32) -> Self {
         Self { r, g, b }
     }
+
+    pub fn luminance(&self) -> f32 {
+        0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
+    }
+
+    pub fn lerp(&self, other: Color, t: f32) -> Color {
+        *self * (1.0 - t) + other * t
+    }
 }
 
 impl Add for Color {
@@ -1,3 +1,4 @@
 pub mod ray;
 pub mod color;
-pub mod camera;
\ No newline at end of file
+pub mod camera;
+pub mod rng;
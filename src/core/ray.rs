@@ -1,18 +1,44 @@
 use crate::math::vector3d::Vector3D;
+
+// Offset rays through the neighbouring pixels in x and y, used to estimate how much of
+// a surface (and of its textures) one pixel covers.
+#[derive(Debug, Copy, Clone)]
+pub struct RayDifferential {
+    pub rx_origin: Vector3D,
+    pub rx_direction: Vector3D,
+    pub ry_origin: Vector3D,
+    pub ry_direction: Vector3D,
+}
+
 #[derive(Clone)]
 pub struct Ray {
     pub origin: Vector3D,
     pub direction: Vector3D,
+    pub differential: Option<RayDifferential>,
+    // Moment within the shutter interval the ray samples, for moving objects
+    pub time: f32,
 }
 impl Ray {
     pub fn new(origin: Vector3D, direction: Vector3D) -> Self {
         Self {
             origin,
             direction: direction.normalize(),
+            differential: None,
+            time: 0.0,
         }
     }
 
+    pub fn with_time(mut self, time: f32) -> Self {
+        self.time = time;
+        self
+    }
+
+    pub fn with_differential(mut self, differential: RayDifferential) -> Self {
+        self.differential = Some(differential);
+        self
+    }
+
     pub fn point_at(&self, t: f32) -> Vector3D {
         self.origin + self.direction * t
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,287 @@
+use std::collections::VecDeque;
+use std::io::{self, BufReader, BufWriter};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::core::color::Color;
+use crate::renderer::renderer::Renderer;
+use crate::renderer::crop::Region;
+use super::protocol::{Request, Response};
+
+// Wait before reconnecting after a connection fails, doubled with every failure in a row
+// up to 64 times as long
+const FIRST_RETRY_DELAY: Duration = Duration::from_millis(250);
+
+// Splits a frame into tiles and farms them out to workers, each address getting
+// `connections` connections that take one tile at a time. A tile that fails, or whose
+// worker goes away, goes back in the queue for another connection, up to `attempts`
+// times in all. A connection that fails is reopened with a growing delay, until it has
+// failed `attempts` times in a row. The result matches a local render exactly.
+pub struct Coordinator {
+    pub workers: Vec<String>,
+    pub connections: u32,
+    pub tile_size: u32,
+    pub attempts: u32,
+    // How long to wait to connect, and for a tile
+    pub timeout: Duration,
+    // Told about failed tiles and connections that are retried
+    pub log: Arc<dyn Fn(&str) + Send + Sync>,
+}
+
+// Tiles still to render, by index, with how often each has failed
+struct Queue {
+    pending: VecDeque<(usize, u32)>,
+    in_flight: usize,
+    error: Option<String>,
+}
+
+struct Shared<'a> {
+    tiles: &'a [Region],
+    queue: Mutex<Queue>,
+    changed: Condvar,
+    attempts: u32,
+    log: &'a (dyn Fn(&str) + Send + Sync),
+}
+
+impl Coordinator {
+    pub fn new(workers: Vec<String>) -> Self {
+        Self { workers, connections: 1, tile_size: 64, attempts: 3, timeout: Duration::from_secs(600), log: Arc::new(|_| {}) }
+    }
+
+    pub fn with_connections(mut self, connections: u32) -> Self {
+        self.connections = connections.max(1);
+        self
+    }
+
+    pub fn with_tile_size(mut self, size: u32) -> Self {
+        self.tile_size = size.max(1);
+        self
+    }
+
+    pub fn with_attempts(mut self, attempts: u32) -> Self {
+        self.attempts = attempts.max(1);
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_log(mut self, log: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        self.log = Arc::new(log);
+        self
+    }
+
+    // Renders `scene`, the text of a scene file, with the size, samples, seed and crop
+    // window of `renderer`. Returns the image of `renderer.region()`.
+    pub fn render(&self, scene: &str, renderer: &Renderer) -> io::Result<Vec<Color>> {
+        let region = renderer.region();
+        let tiles = region.tiles(self.tile_size);
+        let shared = Shared {
+            tiles: &tiles,
+            queue: Mutex::new(Queue { pending: (0..tiles.len()).map(|tile| (tile, 0)).collect(), in_flight: 0, error: None }),
+            changed: Condvar::new(),
+            attempts: self.attempts,
+            log: &*self.log,
+        };
+        let image = Mutex::new(vec![Color::new(0.0, 0.0, 0.0); region.pixel_count()]);
+        let job = Request::Job { scene: scene.to_string(), samples: renderer.samples, seed: renderer.seed };
+
+        thread::scope(|scope| {
+            let (job, shared, region, image) = (&job, &shared, &region, &image);
+            for address in &self.workers {
+                for _ in 0..self.connections {
+                    scope.spawn(move || self.serve_worker(address, job, shared, region, image));
+                }
+            }
+        });
+
+        let queue = shared.queue.into_inner().unwrap();
+        if let Some(error) = queue.error {
+            return Err(io::Error::other(error));
+        }
+        if !queue.pending.is_empty() {
+            return Err(io::Error::other(format!("no workers left, {} of {} tiles not rendered", queue.pending.len(), tiles.len())));
+        }
+        Ok(image.into_inner().unwrap())
+    }
+
+    // One connection's worth of work, reconnecting while tiles are left
+    fn serve_worker(&self, address: &str, job: &Request, shared: &Shared, region: &Region, image: &Mutex<Vec<Color>>) {
+        let mut failures = 0;
+        loop {
+            let error = match self.serve_connection(address, job, shared, region, image, &mut failures) {
+                Ok(()) => return,
+                Err(error) => error,
+            };
+            failures += 1;
+            if failures >= self.attempts {
+                (self.log)(&format!("Worker {} failed {} times in a row, giving up: {}", address, failures, error));
+                return;
+            }
+            let delay = FIRST_RETRY_DELAY * 2u32.saturating_pow(failures - 1).min(64);
+            (self.log)(&format!("Worker {} failed, reconnecting in {} ms: {}", address, delay.as_millis(), error));
+            if !shared.wait_for_work(delay) {
+                return;
+            }
+        }
+    }
+
+    // Takes tiles until there are none left. A tile in hand when the connection fails is
+    // handed back first. Every tile rendered clears `failures`.
+    fn serve_connection(
+        &self,
+        address: &str,
+        job: &Request,
+        shared: &Shared,
+        region: &Region,
+        image: &Mutex<Vec<Color>>,
+        failures: &mut u32,
+    ) -> io::Result<()> {
+        let stream = self.connect(address)?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        let mut input = BufReader::new(stream.try_clone()?);
+        let mut output = BufWriter::new(stream);
+        job.write_to(&mut output)?;
+
+        while let Some((tile, tile_failures)) = shared.next_tile() {
+            let bounds = shared.tiles[tile];
+            let request = Request::Tile { id: tile as u32, region: bounds };
+            let response = request.write_to(&mut output).and_then(|_| Response::read_from(&mut input, bounds.pixel_count()));
+            match response {
+                Ok(Response::Pixels { id, pixels }) if id as usize == tile && pixels.len() == bounds.pixel_count() => {
+                    // Placed relative to the rendered region
+                    let within = Region::new(bounds.x - region.x, bounds.y - region.y, bounds.width, bounds.height);
+                    within.composite(&pixels, &mut image.lock().unwrap(), region.width);
+                    shared.finish();
+                    *failures = 0;
+                }
+                Ok(Response::Failed { id, message }) if id as usize == tile => shared.fail(tile, tile_failures, address, &message),
+                Ok(_) => {
+                    shared.fail(tile, tile_failures, address, "answer does not match the tile");
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, "out of step with the worker"));
+                }
+                Err(error) => {
+                    shared.fail(tile, tile_failures, address, &error.to_string());
+                    return Err(error);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn connect(&self, address: &str) -> io::Result<TcpStream> {
+        let mut last_error = io::Error::new(io::ErrorKind::NotFound, "address did not resolve");
+        for socket in address.to_socket_addrs()? {
+            match TcpStream::connect_timeout(&socket, self.timeout) {
+                Ok(stream) => return Ok(stream),
+                Err(error) => last_error = error,
+            }
+        }
+        Err(last_error)
+    }
+}
+
+impl Shared<'_> {
+    // Waits while other connections still hold tiles that may come back. None once every
+    // tile is done, or the render has failed.
+    fn next_tile(&self) -> Option<(usize, u32)> {
+        let mut queue = self.queue.lock().unwrap();
+        loop {
+            if queue.error.is_some() {
+                return None;
+            }
+            if let Some(tile) = queue.pending.pop_front() {
+                queue.in_flight += 1;
+                return Some(tile);
+            }
+            if queue.in_flight == 0 {
+                return None;
+            }
+            queue = self.changed.wait(queue).unwrap();
+        }
+    }
+
+    // Sleeps for `delay`, cut short when the render is over. False if it is.
+    fn wait_for_work(&self, delay: Duration) -> bool {
+        let deadline = Instant::now() + delay;
+        let mut queue = self.queue.lock().unwrap();
+        loop {
+            let over = queue.error.is_some() || (queue.pending.is_empty() && queue.in_flight == 0);
+            let now = Instant::now();
+            if over || now >= deadline {
+                return !over;
+            }
+            queue = self.changed.wait_timeout(queue, deadline - now).unwrap().0;
+        }
+    }
+
+    fn finish(&self) {
+        self.queue.lock().unwrap().in_flight -= 1;
+        self.changed.notify_all();
+    }
+
+    fn fail(&self, tile: usize, failures: u32, address: &str, message: &str) {
+        let mut queue = self.queue.lock().unwrap();
+        queue.in_flight -= 1;
+        let failures = failures + 1;
+        if failures >= self.attempts {
+            queue.error = Some(format!("tile {} failed {} times, last on {}: {}", tile, failures, address, message));
+        } else {
+            (self.log)(&format!("Tile {} failed on {}, retrying: {}", tile, address, message));
+            queue.pending.push_back((tile, failures));
+        }
+        self.changed.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+    use super::*;
+    use crate::distributed::worker::Worker;
+    use crate::renderer::crop::CropWindow;
+    use crate::scene::scene_file::SceneFile;
+
+    const SCENE: &str = "image width 30 height 20 samples 3
+integrator path depth 3
+camera position 0 1 -6 look_at 0 0 0 up 0 1 0 fov 60
+light position -4 6 -4 color 1 1 1 intensity 1
+sphere center 0 0 0 radius 1
+cube min 1.5 -1 -0.5 max 2.5 0 0.5
+plane point 0 -1 0 normal 0 1 0
+";
+
+    fn start_worker() -> String {
+        let worker = Worker::bind("127.0.0.1:0").unwrap();
+        let address = worker.local_address().unwrap().to_string();
+        thread::spawn(move || worker.serve());
+        address
+    }
+
+    fn bits(image: &[Color]) -> Vec<[u32; 3]> {
+        image.iter().map(|pixel| [pixel.r.to_bits(), pixel.g.to_bits(), pixel.b.to_bits()]).collect()
+    }
+
+    #[test]
+    fn distributed_render_matches_a_local_one() {
+        let file = SceneFile::parse(SCENE).unwrap();
+        let renderer = file.renderer.with_seed(11).with_crop(CropWindow::Pixels { x: 3, y: 2, width: 23, height: 15 });
+        let local = renderer.render(&file.scene, &file.camera);
+
+        let coordinator = Coordinator::new(vec![start_worker(), start_worker()]).with_connections(2).with_tile_size(7);
+        let distributed = coordinator.render(SCENE, &renderer).unwrap();
+        assert_eq!(bits(&distributed), bits(&local));
+    }
+
+    #[test]
+    fn unreachable_workers_fail_the_render() {
+        // A port nobody listens on any more
+        let address = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().to_string();
+        let renderer = SceneFile::parse(SCENE).unwrap().renderer;
+        let coordinator = Coordinator::new(vec![address]).with_attempts(2);
+        assert!(coordinator.render(SCENE, &renderer).is_err());
+    }
+}
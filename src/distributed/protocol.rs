@@ -0,0 +1,160 @@
+use std::io::{self, Read, Write};
+use crate::core::color::Color;
+use crate::renderer::crop::Region;
+
+// Messages between a coordinator and a worker over one TCP connection. The coordinator
+// opens with a Job, then asks for tiles one at a time and closes the connection when the
+// frame is done. Integers and floats are little-endian; floats go as raw bits so tiles
+// match a local render exactly.
+const MAGIC: &[u8; 4] = b"RTDR";
+const VERSION: u32 = 1;
+
+const JOB: u8 = 1;
+const TILE: u8 = 2;
+const PIXELS: u8 = 3;
+const FAILED: u8 = 4;
+
+// Largest scene text or error message accepted, to catch a garbled stream early
+const MAX_TEXT: u32 = 64 << 20;
+
+pub enum Request {
+    // The scene, in scene-file format, with the render settings the command line may
+    // have changed
+    Job { scene: String, samples: u32, seed: u64 },
+    Tile { id: u32, region: Region },
+}
+
+pub enum Response {
+    // The tile's pixels, rows from the bottom
+    Pixels { id: u32, pixels: Vec<Color> },
+    Failed { id: u32, message: String },
+}
+
+impl Request {
+    pub fn write_to(&self, out: &mut impl Write) -> io::Result<()> {
+        match self {
+            Request::Job { scene, samples, seed } => {
+                out.write_all(&[JOB])?;
+                out.write_all(MAGIC)?;
+                out.write_all(&VERSION.to_le_bytes())?;
+                write_text(out, scene)?;
+                out.write_all(&samples.to_le_bytes())?;
+                out.write_all(&seed.to_le_bytes())?;
+            }
+            Request::Tile { id, region } => {
+                out.write_all(&[TILE])?;
+                for value in [*id, region.x, region.y, region.width, region.height] {
+                    out.write_all(&value.to_le_bytes())?;
+                }
+            }
+        }
+        out.flush()
+    }
+
+    // None when the coordinator has closed the connection
+    pub fn read_from(input: &mut impl Read) -> io::Result<Option<Self>> {
+        let mut tag = [0u8; 1];
+        if input.read(&mut tag)? == 0 {
+            return Ok(None);
+        }
+        match tag[0] {
+            JOB => {
+                let mut magic = [0u8; 4];
+                input.read_exact(&mut magic)?;
+                if &magic != MAGIC || read_u32(input)? != VERSION {
+                    return Err(invalid("not a render job, or from another version"));
+                }
+                let scene = read_text(input)?;
+                Ok(Some(Request::Job { scene, samples: read_u32(input)?, seed: read_u64(input)? }))
+            }
+            TILE => {
+                let id = read_u32(input)?;
+                let region = Region::new(read_u32(input)?, read_u32(input)?, read_u32(input)?, read_u32(input)?);
+                Ok(Some(Request::Tile { id, region }))
+            }
+            _ => Err(invalid("unknown request")),
+        }
+    }
+}
+
+impl Response {
+    pub fn write_to(&self, out: &mut impl Write) -> io::Result<()> {
+        match self {
+            Response::Pixels { id, pixels } => {
+                out.write_all(&[PIXELS])?;
+                out.write_all(&id.to_le_bytes())?;
+                out.write_all(&(pixels.len() as u32).to_le_bytes())?;
+                for pixel in pixels {
+                    for channel in [pixel.r, pixel.g, pixel.b] {
+                        out.write_all(&channel.to_bits().to_le_bytes())?;
+                    }
+                }
+            }
+            Response::Failed { id, message } => {
+                out.write_all(&[FAILED])?;
+                out.write_all(&id.to_le_bytes())?;
+                write_text(out, message)?;
+            }
+        }
+        out.flush()
+    }
+
+    // `max_pixels` is the size of the tile asked for; a longer answer is refused before
+    // anything is allocated for it
+    pub fn read_from(input: &mut impl Read, max_pixels: usize) -> io::Result<Self> {
+        let mut tag = [0u8; 1];
+        if input.read(&mut tag)? == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "worker closed the connection"));
+        }
+        match tag[0] {
+            PIXELS => {
+                let id = read_u32(input)?;
+                let count = read_u32(input)?;
+                if count as usize > max_pixels {
+                    return Err(invalid("more pixels than the tile holds"));
+                }
+                let mut pixels = Vec::with_capacity(count as usize);
+                for _ in 0..count {
+                    let r = f32::from_bits(read_u32(input)?);
+                    let g = f32::from_bits(read_u32(input)?);
+                    let b = f32::from_bits(read_u32(input)?);
+                    pixels.push(Color::new(r, g, b));
+                }
+                Ok(Response::Pixels { id, pixels })
+            }
+            FAILED => Ok(Response::Failed { id: read_u32(input)?, message: read_text(input)? }),
+            _ => Err(invalid("unknown response")),
+        }
+    }
+}
+
+fn write_text(out: &mut impl Write, text: &str) -> io::Result<()> {
+    out.write_all(&(text.len() as u32).to_le_bytes())?;
+    out.write_all(text.as_bytes())
+}
+
+fn read_text(input: &mut impl Read) -> io::Result<String> {
+    let length = read_u32(input)?;
+    if length > MAX_TEXT {
+        return Err(invalid("text too long"));
+    }
+    let mut bytes = vec![0u8; length as usize];
+    input.read_exact(&mut bytes)?;
+    String::from_utf8(bytes).map_err(|_| invalid("text is not UTF-8"))
+}
+
+fn read_u32(input: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    input.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(input: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0u8; 8];
+    input.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
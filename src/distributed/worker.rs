@@ -0,0 +1,87 @@
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::thread;
+use crate::scene::scene_file::SceneFile;
+use crate::renderer::renderer::PreparedScene;
+use crate::renderer::crop::{CropWindow, Region};
+use super::protocol::{Request, Response};
+
+// Renders tiles for coordinators. Each connection gets its own thread and scene, so one
+// worker can serve several connections, and coordinators, at once.
+pub struct Worker {
+    listener: TcpListener,
+    // Told about connections that failed; they don't stop the worker
+    log: Arc<dyn Fn(&str) + Send + Sync>,
+}
+
+impl Worker {
+    pub fn bind(address: impl ToSocketAddrs) -> io::Result<Self> {
+        Ok(Self { listener: TcpListener::bind(address)?, log: Arc::new(|_| {}) })
+    }
+
+    pub fn with_log(mut self, log: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        self.log = Arc::new(log);
+        self
+    }
+
+    pub fn local_address(&self) -> io::Result<std::net::SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    // Serves connections until the listener fails
+    pub fn serve(&self) -> io::Result<()> {
+        for stream in self.listener.incoming() {
+            let stream = stream?;
+            let log = Arc::clone(&self.log);
+            thread::spawn(move || {
+                let peer = stream.peer_addr().map(|address| address.to_string()).unwrap_or_default();
+                if let Err(error) = handle_connection(stream) {
+                    log(&format!("Connection from {} failed: {}", peer, error));
+                }
+            });
+        }
+        Ok(())
+    }
+}
+
+// The scene of each job is loaded and prepared once for all its tiles. A scene that
+// failed to load answers every tile with the error, so the coordinator sees why.
+fn handle_connection(stream: TcpStream) -> io::Result<()> {
+    let mut input = BufReader::new(stream.try_clone()?);
+    let mut output = BufWriter::new(stream);
+
+    let mut next = serve_tiles(&mut input, &mut output, |id, _| Response::Failed { id, message: "tile asked for before the scene".to_string() })?;
+    while let Some(Request::Job { scene, samples, seed }) = next {
+        next = match SceneFile::parse(&scene) {
+            Ok(file) => {
+                let instances = file.scene.root.flatten();
+                let prepared = PreparedScene::new(&file.scene, &instances);
+                let mut renderer = file.renderer.with_samples(samples).with_seed(seed);
+                serve_tiles(&mut input, &mut output, |id, region| {
+                    if region.x.saturating_add(region.width) > renderer.width || region.y.saturating_add(region.height) > renderer.height {
+                        return Response::Failed { id, message: format!("tile {:?} is outside the {}x{} frame", region, renderer.width, renderer.height) };
+                    }
+                    renderer.crop = Some(CropWindow::from_region(&region, renderer.height));
+                    Response::Pixels { id, pixels: renderer.render_prepared(&prepared, &file.camera) }
+                })?
+            }
+            Err(error) => {
+                let message = format!("scene: {}", error);
+                serve_tiles(&mut input, &mut output, |id, _| Response::Failed { id, message: message.clone() })?
+            }
+        };
+    }
+    Ok(())
+}
+
+// Answers tile requests with `render` until the next job, which is returned, or the end
+// of the connection
+fn serve_tiles(input: &mut impl Read, output: &mut impl Write, mut render: impl FnMut(u32, Region) -> Response) -> io::Result<Option<Request>> {
+    loop {
+        match Request::read_from(input)? {
+            Some(Request::Tile { id, region }) => render(id, region).write_to(output)?,
+            other => return Ok(other),
+        }
+    }
+}
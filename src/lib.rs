@@ -0,0 +1,11 @@
+pub mod math;
+pub mod core;
+pub mod objects;
+pub mod textures;
+pub mod cameras;
+pub mod animation;
+pub mod materials;
+pub mod media;
+pub mod scene;
+pub mod renderer;
+pub mod distributed;
@@ -1,24 +1,108 @@
-mod math;
-mod core;
-mod objects;
-mod scene;
-mod renderer;
-
-use math::vector3d::Vector3D;
-use core::color::Color;
-use core::camera::Camera;
-use objects::sphere::Sphere;
-use objects::plane::Plane;
-use objects::cylinder::Cylinder;
-use objects::cube::Cube;
-use scene::scene::Scene;
-use scene::light::Light;
-use renderer::renderer::Renderer;
-
-use std::fs::File;
+use rt::math::vector3d::Vector3D;
+use rt::core::color::Color;
+use rt::core::camera::Camera;
+use rt::objects::sphere::Sphere;
+use rt::objects::plane::Plane;
+use rt::objects::cylinder::Cylinder;
+use rt::objects::cube::Cube;
+use rt::scene::scene::Scene;
+use rt::scene::light::Light;
+use rt::renderer::renderer::{Integrator, Renderer};
+use rt::renderer::path_tracer::PathTracer;
+use rt::math::transform::Transform;
+use rt::math::motion::Motion;
+use rt::scene::node::Node;
+use rt::animation::animation::Animation;
+use rt::math::track::{Interpolation, Track};
+use rt::animation::sequence::Sequence;
+use rt::scene::scene_file::{parse_camera_path, SceneFile};
+use rt::renderer::output::{composite_png, save_png};
+use rt::renderer::crop::{CropWindow, Region};
+use rt::renderer::progressive::Progressive;
+use rt::renderer::accumulation::Accumulation;
+use rt::renderer::checkpoint::{load_checkpoint, CheckpointKey};
+use rt::distributed::coordinator::Coordinator;
+use rt::distributed::worker::Worker;
+
+use clap::{Arg, ArgMatches, Command};
+use std::fs::{self, File};
 use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 
 fn main() {
+    let matches = Command::new("rt")
+        .about("Ray tracer")
+        .subcommand(
+            Command::new("animate")
+                .about("Renders the animated demo scene to numbered PNG files")
+                .arg(Arg::new("first").long("first").takes_value(true).default_value("1").help("First frame"))
+                .arg(Arg::new("last").long("last").takes_value(true).default_value("48").help("Last frame"))
+                .arg(Arg::new("fps").long("fps").takes_value(true).default_value("24").help("Frames per second"))
+                .arg(Arg::new("shutter").long("shutter").takes_value(true).default_value("0.5")
+                    .help("Fraction of a frame the shutter is open for motion blur"))
+                .arg(Arg::new("output").long("output").short('o').takes_value(true).default_value("frames")
+                    .help("Directory for frame_0001.png, frame_0002.png, ..."))
+                .arg(Arg::new("resume").long("resume").help("Skip frames already rendered"))
+                .arg(Arg::new("width").long("width").takes_value(true).default_value("400"))
+                .arg(Arg::new("height").long("height").takes_value(true).default_value("300"))
+                .arg(Arg::new("samples").long("samples").takes_value(true).default_value("16").help("Samples per pixel")),
+        )
+        .subcommand(
+            Command::new("render")
+                .about("Renders a scene file to a PNG image, or to an image sequence along a camera path")
+                .arg(Arg::new("scene").required(true).help("Scene file"))
+                .arg(Arg::new("output").long("output").short('o').takes_value(true)
+                    .help("Image file, or directory for a camera path [default: render.png or frames]"))
+                .arg(Arg::new("path").long("path").takes_value(true)
+                    .help("Camera path replacing the scene file's, e.g. \"orbit frames 36\" or \"dolly to 0 2 -4 frames 24\""))
+                .arg(Arg::new("resume").long("resume")
+                    .help("Skip frames already rendered, or continue a single image from its checkpoint"))
+                .arg(Arg::new("samples").long("samples").takes_value(true).help("Samples per pixel, overriding the scene file's"))
+                .arg(Arg::new("progressive").long("progressive")
+                    .help("Render in passes, saving the image so far every --dump-every seconds"))
+                .arg(Arg::new("pass-samples").long("pass-samples").takes_value(true).default_value("1")
+                    .help("Samples per pixel in each progressive pass"))
+                .arg(Arg::new("dump-every").long("dump-every").takes_value(true).default_value("10")
+                    .help("Seconds between progressive image dumps"))
+                .arg(Arg::new("checkpoint").long("checkpoint")
+                    .help("Save the render state to <output>.checkpoint (one per crop) every --checkpoint-every seconds"))
+                .arg(Arg::new("crop").long("crop").takes_value(true).value_name("X,Y,W,H")
+                    .help("Render only this pixel rectangle, measured from the top-left corner"))
+                .arg(Arg::new("crop-fraction").long("crop-fraction").takes_value(true).value_name("L,T,R,B")
+                    .conflicts_with("crop").help("Render only this part of the frame, as fractions of its size"))
+                .arg(Arg::new("composite").long("composite")
+                    .help("Paste the cropped render into the existing output image instead of writing it on its own"))
+                .arg(Arg::new("checkpoint-every").long("checkpoint-every").takes_value(true).default_value("60")
+                    .help("Seconds between checkpoints"))
+                .arg(Arg::new("workers").long("workers").takes_value(true).value_name("HOST:PORT,...")
+                    .conflicts_with_all(&["progressive", "checkpoint", "resume"])
+                    .help("Render the image in tiles on these workers (see the worker command)"))
+                .arg(Arg::new("connections").long("connections").takes_value(true).default_value("1")
+                    .help("Tiles each worker renders at once"))
+                .arg(Arg::new("tile-size").long("tile-size").takes_value(true).default_value("64")
+                    .help("Width and height of the tiles sent to workers"))
+                .arg(Arg::new("attempts").long("attempts").takes_value(true).default_value("3")
+                    .help("Times a tile is tried before the render fails, and a lost worker is reconnected to")),
+        )
+        .subcommand(
+            Command::new("worker")
+                .about("Renders tiles for `render --workers`, which sends the scene along")
+                .arg(Arg::new("listen").long("listen").takes_value(true).default_value("127.0.0.1:7878")
+                    .help("Address to listen on; use 0.0.0.0:PORT to accept other machines")),
+        )
+        .get_matches();
+
+    match matches.subcommand() {
+        Some(("animate", args)) => render_animation(args),
+        Some(("render", args)) => render_scene_file(args),
+        Some(("worker", args)) => run_worker(args),
+        _ => render_stills(),
+    }
+}
+
+fn render_stills() {
     let width = 800;
     let height = 600;
 
@@ -89,6 +173,201 @@ fn main() {
     println!("All scenes rendered successfully!");
 }
 
+fn parse<T: std::str::FromStr>(args: &ArgMatches, name: &str) -> T {
+    let value = args.value_of(name).expect("Argument has a default");
+    value.parse().unwrap_or_else(|_| panic!("Invalid value for --{}: {}", name, value))
+}
+
+fn render_scene_file(args: &ArgMatches) {
+    let scene_path = args.value_of("scene").expect("Scene file is required");
+    let SceneFile { mut scene, mut camera, mut renderer, camera_path } =
+        SceneFile::load(&PathBuf::from(scene_path)).unwrap_or_else(|error| panic!("{}: {}", scene_path, error));
+    if args.is_present("samples") {
+        renderer = renderer.with_samples(parse(args, "samples"));
+    }
+    if args.is_present("crop") {
+        let [x, y, width, height] = parse_list(args, "crop");
+        renderer = renderer.with_crop(CropWindow::Pixels { x, y, width, height });
+    } else if args.is_present("crop-fraction") {
+        let [left, top, right, bottom] = parse_list(args, "crop-fraction");
+        renderer = renderer.with_crop(CropWindow::Normalized { left, top, right, bottom });
+    }
+    let camera_path = match args.value_of("path") {
+        Some(text) => Some(parse_camera_path(text, &camera).unwrap_or_else(|error| panic!("--path: {}", error))),
+        None => camera_path,
+    };
+
+    let path = match camera_path {
+        Some(path) => path,
+        None => {
+            let output = PathBuf::from(args.value_of("output").unwrap_or("render.png"));
+            if let Some(workers) = args.value_of("workers") {
+                let coordinator = Coordinator::new(workers.split(',').map(|worker| worker.trim().to_string()).collect())
+                    .with_connections(parse(args, "connections"))
+                    .with_tile_size(parse(args, "tile-size"))
+                    .with_attempts(parse(args, "attempts"))
+                    .with_log(|message| eprintln!("{}", message));
+                let text = fs::read_to_string(scene_path).unwrap_or_else(|error| panic!("{}: {}", scene_path, error));
+                let image = coordinator.render(&text, &renderer).unwrap_or_else(|error| panic!("Distributed render failed: {}", error));
+                save_render(&output, &image, &renderer, args.is_present("composite"));
+                println!("Rendered scene saved as {}", output.display());
+                return;
+            }
+            let checkpoint = checkpoint_path(&output, &renderer);
+            let checkpointing = args.is_present("checkpoint") || args.is_present("resume");
+            let key = checkpointing.then(|| {
+                let text = fs::read_to_string(scene_path).unwrap_or_else(|error| panic!("{}: {}", scene_path, error));
+                CheckpointKey::new(&renderer, &text)
+            });
+            let composite = args.is_present("composite");
+            if args.is_present("progressive") || checkpointing {
+                let mut progressive = Progressive::new()
+                    .with_pass_samples(parse(args, "pass-samples"))
+                    .with_composite(composite);
+                if args.is_present("progressive") {
+                    progressive = progressive.with_dumps(output.clone(), Duration::from_secs_f32(parse(args, "dump-every")));
+                }
+                if let Some(key) = key {
+                    progressive = progressive.with_checkpoints(checkpoint.clone(), key, Duration::from_secs_f32(parse(args, "checkpoint-every")));
+                }
+                let mut accumulation = match &key {
+                    Some(key) if args.is_present("resume") && checkpoint.exists() => resume_checkpoint(&checkpoint, key, &renderer),
+                    _ => renderer.accumulation(),
+                };
+                let image = progressive.render(&renderer, &scene, &camera, &mut accumulation).expect("Failed to save image");
+                if !args.is_present("progressive") {
+                    save_render(&output, &image, &renderer, composite);
+                }
+            } else {
+                let image = renderer.render(&scene, &camera);
+                save_render(&output, &image, &renderer, composite);
+            }
+            println!("Rendered scene saved as {}", output.display());
+            return;
+        }
+    };
+
+    if args.is_present("workers") {
+        panic!("--workers renders single images; drop the camera path");
+    }
+    let sequence = Sequence::new(1, path.frames(), 24.0, PathBuf::from(args.value_of("output").unwrap_or("frames")))
+        .with_camera_path(path)
+        .with_resume(args.is_present("resume"));
+    for frame in sequence.frames() {
+        if !sequence.needs_render(frame) {
+            println!("Skipping frame {}, already rendered", frame);
+            continue;
+        }
+        let path = sequence
+            .render_frame(frame, &renderer, &mut scene, &mut camera, &Animation::new())
+            .expect("Failed to save frame");
+        println!("Rendered frame {} saved as {}", frame, path.display());
+    }
+}
+
+fn run_worker(args: &ArgMatches) {
+    let address = args.value_of("listen").expect("Argument has a default");
+    let worker = Worker::bind(address)
+        .unwrap_or_else(|error| panic!("Cannot listen on {}: {}", address, error))
+        .with_log(|message| eprintln!("{}", message));
+    println!("Worker listening on {}", worker.local_address().map_or_else(|_| address.to_string(), |address| address.to_string()));
+    worker.serve().expect("Worker stopped");
+}
+
+// Comma separated values, e.g. --crop 100,50,200,150
+fn parse_list<T: std::str::FromStr, const N: usize>(args: &ArgMatches, name: &str) -> [T; N] {
+    let value = args.value_of(name).expect("Argument is present");
+    let values: Vec<T> = value
+        .split(',')
+        .map(|part| part.trim().parse().unwrap_or_else(|_| panic!("Invalid value for --{}: {}", name, value)))
+        .collect();
+    values.try_into().unwrap_or_else(|_| panic!("--{} takes {} comma separated values, got {}", name, N, value))
+}
+
+// The render on its own, or pasted into the full frame already at `output`
+fn save_render(output: &Path, image: &[Color], renderer: &Renderer, composite: bool) {
+    let region = renderer.region();
+    let result = if composite {
+        composite_png(output, image, &region, renderer.width, renderer.height)
+    } else {
+        save_png(output, image, region.width, region.height)
+    };
+    result.expect("Failed to save image");
+}
+
+// <output>.checkpoint, or for a crop <output>.crop-X-Y-WxH.checkpoint with the crop's
+// pixels from the top-left corner, so crops of one frame can be checkpointed side by side
+fn checkpoint_path(output: &Path, renderer: &Renderer) -> PathBuf {
+    let region = renderer.region();
+    if region == Region::full(renderer.width, renderer.height) {
+        return output.with_extension("checkpoint");
+    }
+    let top = renderer.height - region.y - region.height;
+    output.with_extension(format!("crop-{}-{}-{}x{}.checkpoint", region.x, top, region.width, region.height))
+}
+
+fn resume_checkpoint(path: &Path, key: &CheckpointKey, renderer: &Renderer) -> Accumulation {
+    let accumulation = load_checkpoint(path, key).unwrap_or_else(|error| panic!("{}: {}", path.display(), error));
+    if accumulation.samples > renderer.samples {
+        panic!("{}: checkpoint already has {} samples per pixel, more than the {} asked for", path.display(), accumulation.samples, renderer.samples);
+    }
+    println!("Resuming from {} at {}/{} samples per pixel", path.display(), accumulation.samples, renderer.samples);
+    accumulation
+}
+
+// Spheres circling a pillar while the camera cranes up and the light fades in
+fn render_animation(args: &ArgMatches) {
+    let width: u32 = parse(args, "width");
+    let height: u32 = parse(args, "height");
+    let sequence = Sequence::new(parse(args, "first"), parse(args, "last"), parse(args, "fps"), PathBuf::from(args.value_of("output").unwrap_or("frames")))
+        .with_shutter(parse(args, "shutter"))
+        .with_resume(args.is_present("resume"));
+    let duration = sequence.frame_time(sequence.last).max(1e-3);
+
+    let mut scene = Scene::new();
+    scene.add_object(Box::new(Plane::new(Vector3D::new(0.0, -1.0, 0.0), Vector3D::new(0.0, 1.0, 0.0))));
+    scene.add_object(Box::new(Cylinder::new(Vector3D::new(0.0, -1.0, 0.0), Vector3D::new(0.0, 1.0, 0.0), 0.5, 2.0)));
+    let mut orbit = Motion::new();
+    for step in 0..=4 {
+        let angle = 360.0 * step as f32 / 4.0;
+        orbit.add_key(duration * step as f32 / 4.0, Transform::rotate(Vector3D::new(0.0, 1.0, 0.0), angle), Interpolation::Linear);
+    }
+    let mut carousel = Node::group("carousel").with_motion(orbit);
+    for i in 0..3 {
+        let angle = i as f32 * 120.0_f32.to_radians();
+        let center = Vector3D::new(2.0 * angle.cos(), -0.5, 2.0 * angle.sin());
+        carousel.add_child(Node::geometry("ball", Arc::new(Sphere::new(center, 0.5))));
+    }
+    scene.root.add_child(carousel);
+    scene.add_light(Light::new(Vector3D::new(-5.0, 8.0, -5.0), Color::new(1.0, 1.0, 1.0), 1.0));
+
+    let animation = Animation::new()
+        .with_camera_position(Track::new()
+            .with_key(0.0, Vector3D::new(0.0, 0.5, -7.0), Interpolation::Bezier)
+            .with_key(0.5 * duration, Vector3D::new(3.0, 3.0, -5.0), Interpolation::Bezier)
+            .with_key(duration, Vector3D::new(0.0, 6.0, -4.0), Interpolation::Bezier))
+        .with_camera_look_at(Track::constant(Vector3D::new(0.0, 0.0, 0.0)))
+        .with_light_intensity(0, Track::new()
+            .with_key(0.0, 0.2, Interpolation::Bezier)
+            .with_key(duration, 1.0, Interpolation::Bezier));
+
+    let mut camera = Camera::new(Vector3D::new(0.0, 0.5, -7.0), Vector3D::new(0.0, 0.0, 0.0), Vector3D::new(0.0, 1.0, 0.0), 60.0, width as f32 / height as f32);
+    let renderer = Renderer::new(width, height)
+        .with_integrator(Integrator::PathTracer(PathTracer::new()))
+        .with_samples(parse(args, "samples"));
+
+    for frame in sequence.frames() {
+        if !sequence.needs_render(frame) {
+            println!("Skipping frame {}, already rendered", frame);
+            continue;
+        }
+        let path = sequence
+            .render_frame(frame, &renderer, &mut scene, &mut camera, &animation)
+            .expect("Failed to save frame");
+        println!("Rendered frame {} saved as {}", frame, path.display());
+    }
+}
+
 fn render_scene(scene: &Scene, camera: &Camera, width: u32, height: u32, filename: &str) {
     let renderer = Renderer::new(width, height);
     let image = renderer.render(scene, camera);
@@ -107,9 +386,9 @@ fn save_image(image: &[Color], width: u32, height: u32, filename: &str) {
         for x in 0..width {
             let index = ((height - 1 - y) * width + x) as usize; // Inversion des coordonnées y
             let color = &image[index];
-            let r = (color.r.min(1.0).max(0.0) * 100.0) as u8;
-            let g = (color.g.min(1.0).max(0.0) * 200.0) as u8;
-            let b = (color.b.min(1.0).max(0.0) * 255.0) as u8;
+            let r = (color.r.clamp(0.0, 1.0) * 100.0) as u8;
+            let g = (color.g.clamp(0.0, 1.0) * 200.0) as u8;
+            let b = (color.b.clamp(0.0, 1.0) * 255.0) as u8;
             write!(file, "{} {} {} ", r, g, b).expect("Failed to write pixel data");
         }
         writeln!(file).expect("Failed to write newline");
@@ -0,0 +1,135 @@
+use std::f32::consts::PI;
+use crate::math::vector3d::Vector3D;
+use crate::core::color::Color;
+use super::microfacet::{fresnel_schlick, Ggx};
+
+// Orthonormal shading frame: BSDFs work with directions expressed in it, the normal
+// along +z and the surface's u direction along +x.
+#[derive(Debug, Copy, Clone)]
+pub struct Frame {
+    pub tangent: Vector3D,
+    pub bitangent: Vector3D,
+    pub normal: Vector3D,
+}
+
+impl Frame {
+    pub fn new(normal: Vector3D, tangent: &Vector3D) -> Self {
+        let normal = normal.normalize();
+        let projected = *tangent - normal * tangent.dot(&normal);
+        let tangent = if projected.length() > 1e-6 {
+            projected.normalize()
+        } else {
+            normal.orthonormal_basis().0
+        };
+        Self { tangent, bitangent: normal.cross(&tangent), normal }
+    }
+
+    pub fn to_local(&self, v: &Vector3D) -> Vector3D {
+        Vector3D::new(v.dot(&self.tangent), v.dot(&self.bitangent), v.dot(&self.normal))
+    }
+
+    pub fn to_world(&self, v: &Vector3D) -> Vector3D {
+        self.tangent * v.x + self.bitangent * v.y + self.normal * v.z
+    }
+}
+
+pub struct BsdfSample {
+    pub wi: Vector3D,
+    // BSDF value times the cosine term over the pdf, what the path throughput is scaled by
+    pub weight: Color,
+    pub pdf: f32,
+}
+
+pub fn cosine_hemisphere(u1: f32, u2: f32) -> Vector3D {
+    let r = u1.sqrt();
+    let phi = 2.0 * PI * u2;
+    Vector3D::new(r * phi.cos(), r * phi.sin(), (1.0 - u1).max(0.0).sqrt())
+}
+
+// Scattering at a surface point, directions in the local shading frame with `wo` on the
+// +z side. Directions with negative z are transmitted through the surface.
+pub trait Bsdf {
+    fn eval(&self, wo: &Vector3D, wi: &Vector3D) -> Color;
+
+    fn pdf(&self, wo: &Vector3D, wi: &Vector3D) -> f32;
+
+    // `u` picks a lobe, `u1` and `u2` a direction within it
+    fn sample(&self, wo: &Vector3D, u: f32, u1: f32, u2: f32) -> Option<BsdfSample>;
+}
+
+// Wraps a sampled direction and its density into a sample, or nothing if it can't be used
+pub fn bsdf_sample(bsdf: &dyn Bsdf, wo: &Vector3D, wi: Vector3D) -> Option<BsdfSample> {
+    let pdf = bsdf.pdf(wo, &wi);
+    if pdf <= 0.0 {
+        return None;
+    }
+    Some(BsdfSample { wi, weight: bsdf.eval(wo, &wi) * (wi.z.abs() / pdf), pdf })
+}
+
+// glTF's metallic-roughness BRDF: a Lambertian base under a Cook–Torrance GGX specular
+// lobe. Metals tint the reflection with the base colour and lose the diffuse part,
+// dielectrics reflect 4% at normal incidence.
+pub struct MetallicRoughness {
+    pub base_color: Color,
+    pub metallic: f32,
+    pub distribution: Ggx,
+}
+
+impl MetallicRoughness {
+    pub fn new(base_color: Color, metallic: f32, roughness: f32) -> Self {
+        Self { base_color, metallic: metallic.clamp(0.0, 1.0), distribution: Ggx::from_roughness(roughness) }
+    }
+
+    fn f0(&self) -> Color {
+        Color::new(0.04, 0.04, 0.04).lerp(self.base_color, self.metallic)
+    }
+
+    fn diffuse_color(&self) -> Color {
+        self.base_color * (1.0 - self.metallic)
+    }
+
+    // Odds of sampling the specular lobe, following how much each lobe reflects
+    fn specular_probability(&self, wo: &Vector3D) -> f32 {
+        let specular = fresnel_schlick(self.f0(), wo.z).luminance();
+        let diffuse = self.diffuse_color().luminance();
+        if specular + diffuse <= 0.0 {
+            return 1.0;
+        }
+        specular / (specular + diffuse)
+    }
+}
+
+impl Bsdf for MetallicRoughness {
+    fn eval(&self, wo: &Vector3D, wi: &Vector3D) -> Color {
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        let h = (*wo + *wi).normalize();
+        let fresnel = fresnel_schlick(self.f0(), wi.dot(&h));
+        let specular = fresnel * self.distribution.reflectance(wo, wi);
+
+        let kd = Color::new(1.0 - fresnel.r, 1.0 - fresnel.g, 1.0 - fresnel.b);
+        let diffuse = kd * self.diffuse_color() * (1.0 / PI);
+        specular + diffuse
+    }
+
+    fn pdf(&self, wo: &Vector3D, wi: &Vector3D) -> f32 {
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return 0.0;
+        }
+        let p = self.specular_probability(wo);
+        p * self.distribution.reflection_pdf(wo, wi) + (1.0 - p) * wi.z / PI
+    }
+
+    fn sample(&self, wo: &Vector3D, u: f32, u1: f32, u2: f32) -> Option<BsdfSample> {
+        if wo.z <= 0.0 {
+            return None;
+        }
+        let wi = if u < self.specular_probability(wo) {
+            self.distribution.sample_reflection(wo, u1, u2)
+        } else {
+            cosine_hemisphere(u1, u2)
+        };
+        bsdf_sample(self, wo, wi)
+    }
+}
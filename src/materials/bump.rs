@@ -0,0 +1,90 @@
+use std::sync::Arc;
+use crate::math::vector3d::Vector3D;
+use crate::objects::object::Intersection;
+use crate::textures::texture::Texture;
+use crate::textures::image_texture::ImageTexture;
+
+// Shading normal tilted within the intersection's tangent frame, kept on the
+// geometric normal's side so lighting never flips.
+fn orient(shading: Vector3D, geometric: &Vector3D) -> Vector3D {
+    if shading.dot(geometric) < 0.0 {
+        -shading
+    } else {
+        shading
+    }
+}
+
+// Tangent-space normal map: RGB in [0, 1] encodes a normal whose blue axis follows the
+// surface normal, red follows the tangent (u) and green the bitangent (v).
+pub struct NormalMap {
+    pub texture: ImageTexture,
+    pub strength: f32,
+}
+
+impl NormalMap {
+    pub fn new(texture: ImageTexture) -> Self {
+        Self { texture, strength: 1.0 }
+    }
+
+    pub fn open(path: &str) -> Result<Self, image::ImageError> {
+        Ok(Self::new(ImageTexture::open(path)?))
+    }
+
+    pub fn with_strength(mut self, strength: f32) -> Self {
+        self.strength = strength;
+        self
+    }
+
+    pub fn perturb(&self, hit: &Intersection) -> Vector3D {
+        let (u, v) = hit.uv;
+        let texel = self.texture.sample(u, v);
+        let x = (texel.r * 2.0 - 1.0) * self.strength;
+        let y = (texel.g * 2.0 - 1.0) * self.strength;
+        let z = texel.b * 2.0 - 1.0;
+
+        // Gram-Schmidt so the frame stays orthonormal around the geometric normal
+        let n = hit.normal.normalize();
+        let t = (hit.tangent - n * hit.tangent.dot(&n)).normalize();
+        let b = n.cross(&t);
+        let b = if b.dot(&hit.bitangent) < 0.0 { -b } else { b };
+        orient((t * x + b * y + n * z).normalize(), &n)
+    }
+}
+
+// Bump map driven by any texture, read as a height field (mean of the RGB channels).
+// Procedural textures make bumps without image files.
+pub struct BumpMap {
+    pub height: Arc<dyn Texture>,
+    pub scale: f32,
+}
+
+impl BumpMap {
+    pub fn new(height: Arc<dyn Texture>, scale: f32) -> Self {
+        Self { height, scale }
+    }
+
+    fn height_at(&self, u: f32, v: f32, point: &Vector3D) -> f32 {
+        let c = self.height.value(u, v, point);
+        (c.r + c.g + c.b) / 3.0 * self.scale
+    }
+
+    // Displaces the surface along its normal by the height and rebuilds the normal from
+    // finite differences along the tangent frame.
+    pub fn perturb(&self, hit: &Intersection) -> Vector3D {
+        let (u, v) = hit.uv;
+        let n = hit.normal.normalize();
+        let (du, dv) = (1e-3, 1e-3);
+
+        let h = self.height_at(u, v, &hit.point);
+        let h_u = self.height_at(u + du, v, &(hit.point + hit.tangent * du));
+        let h_v = self.height_at(u, v + dv, &(hit.point + hit.bitangent * dv));
+
+        let tangent = hit.tangent + n * ((h_u - h) / du);
+        let bitangent = hit.bitangent + n * ((h_v - h) / dv);
+        let shading = tangent.cross(&bitangent);
+        if shading.length() < 1e-12 {
+            return n;
+        }
+        orient(shading.normalize(), &n)
+    }
+}
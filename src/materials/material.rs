@@ -0,0 +1,169 @@
+use std::sync::Arc;
+use crate::math::vector3d::Vector3D;
+use crate::core::color::Color;
+use crate::objects::object::Intersection;
+use crate::textures::texture::{Footprint, SolidColor, Texture};
+use super::bump::{BumpMap, NormalMap};
+use super::bsdf::{Bsdf, MetallicRoughness};
+use super::principled::{Layers, Principled};
+
+#[derive(Clone)]
+pub struct Material {
+    pub albedo: Arc<dyn Texture>,
+    pub normal_map: Option<Arc<NormalMap>>,
+    pub bump_map: Option<Arc<BumpMap>>,
+    // Fraction of the colour coming from the mirror reflection, 0 for matte surfaces
+    pub reflectivity: f32,
+    // glTF metallic-roughness factors, used by the path tracer
+    pub metallic: f32,
+    pub roughness: f32,
+    // Scales the factors per texel like glTF's metallicRoughnessTexture: green holds
+    // roughness, blue metalness
+    pub metallic_roughness: Option<Arc<dyn Texture>>,
+    // Clearcoat, sheen, anisotropy and the like; the plain glTF model when absent
+    pub layers: Option<Layers>,
+    // Light given off by the front of the surface, looked up like the albedo and scaled
+    // by `emission_strength`; any emission makes it a lamp
+    pub emission: Option<Arc<dyn Texture>>,
+    pub emission_strength: f32,
+}
+
+impl Material {
+    pub fn new(color: Color) -> Self {
+        Self::textured(Arc::new(SolidColor::new(color)))
+    }
+
+    pub fn textured(albedo: Arc<dyn Texture>) -> Self {
+        Self {
+            albedo,
+            normal_map: None,
+            bump_map: None,
+            reflectivity: 0.0,
+            metallic: 0.0,
+            roughness: 1.0,
+            metallic_roughness: None,
+            layers: None,
+            emission: None,
+            emission_strength: 1.0,
+        }
+    }
+
+    pub fn with_normal_map(mut self, normal_map: Arc<NormalMap>) -> Self {
+        self.normal_map = Some(normal_map);
+        self
+    }
+
+    pub fn with_bump_map(mut self, bump_map: Arc<BumpMap>) -> Self {
+        self.bump_map = Some(bump_map);
+        self
+    }
+
+    pub fn with_reflectivity(mut self, reflectivity: f32) -> Self {
+        self.reflectivity = reflectivity.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_metallic(mut self, metallic: f32) -> Self {
+        self.metallic = metallic.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_roughness(mut self, roughness: f32) -> Self {
+        self.roughness = roughness.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_metallic_roughness_texture(mut self, texture: Arc<dyn Texture>) -> Self {
+        self.metallic_roughness = Some(texture);
+        self
+    }
+
+    pub fn with_layers(mut self, layers: Layers) -> Self {
+        self.layers = Some(layers);
+        self
+    }
+
+    // Black, or no strength, leaves the surface dark
+    pub fn with_emission(self, color: Color, strength: f32) -> Self {
+        if color.luminance() <= 0.0 {
+            return self;
+        }
+        self.with_emission_texture(Arc::new(SolidColor::new(color)), strength)
+    }
+
+    pub fn with_emission_texture(mut self, texture: Arc<dyn Texture>, strength: f32) -> Self {
+        self.emission = Some(texture);
+        self.emission_strength = strength.max(0.0);
+        self
+    }
+
+    pub fn is_emissive(&self) -> bool {
+        self.emission.is_some() && self.emission_strength > 0.0
+    }
+
+    // Radiance given off at a point of the surface, front or back
+    pub fn emission_at(&self, u: f32, v: f32, point: &Vector3D) -> Color {
+        match &self.emission {
+            Some(texture) => texture.value(u, v, point) * self.emission_strength,
+            None => Color::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    // Radiance leaving the surface towards `direction`, nothing from the back
+    pub fn emitted(&self, hit: &Intersection, direction: &Vector3D) -> Color {
+        if self.is_emissive() && hit.normal.dot(direction) > 0.0 {
+            let (u, v) = hit.uv;
+            self.emission_at(u, v, &hit.point)
+        } else {
+            Color::new(0.0, 0.0, 0.0)
+        }
+    }
+
+    pub fn base_color(&self, hit: &Intersection, footprint: Option<&Footprint>) -> Color {
+        let (u, v) = hit.uv;
+        match footprint {
+            Some(footprint) => self.albedo.filtered_value(u, v, &hit.point, footprint),
+            None => self.albedo.value(u, v, &hit.point),
+        }
+    }
+
+    pub fn bsdf(&self, hit: &Intersection, footprint: Option<&Footprint>) -> Box<dyn Bsdf> {
+        let (mut metallic, mut roughness) = (self.metallic, self.roughness);
+        if let Some(texture) = &self.metallic_roughness {
+            let (u, v) = hit.uv;
+            let texel = texture.value(u, v, &hit.point);
+            metallic *= texel.b;
+            roughness *= texel.g;
+        }
+        let base_color = self.base_color(hit, footprint);
+        match &self.layers {
+            Some(layers) => {
+                let mut principled = Principled::new(base_color, metallic, roughness, layers.clone());
+                if let Some(texture) = &layers.sheen_color {
+                    let (u, v) = hit.uv;
+                    principled = principled.with_sheen_color(texture.value(u, v, &hit.point));
+                }
+                Box::new(principled)
+            }
+            None => Box::new(MetallicRoughness::new(base_color, metallic, roughness)),
+        }
+    }
+
+    // Normal used for lighting. The geometric normal of the hit stays untouched for
+    // offsetting secondary rays.
+    pub fn shading_normal(&self, hit: &Intersection) -> Vector3D {
+        if let Some(normal_map) = &self.normal_map {
+            normal_map.perturb(hit)
+        } else if let Some(bump_map) = &self.bump_map {
+            bump_map.perturb(hit)
+        } else {
+            hit.normal
+        }
+    }
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self::new(Color::new(1.0, 1.0, 1.0))
+    }
+}
@@ -0,0 +1,116 @@
+use std::f32::consts::PI;
+use crate::math::vector3d::Vector3D;
+use crate::core::color::Color;
+
+// Smallest roughness used, a perfectly smooth GGX lobe being a delta the sampler
+// can't evaluate
+const MIN_ALPHA: f32 = 1e-3;
+
+pub fn fresnel_schlick(f0: Color, cos_theta: f32) -> Color {
+    let weight = (1.0 - cos_theta.clamp(0.0, 1.0)).powi(5);
+    f0.lerp(Color::new(1.0, 1.0, 1.0), weight)
+}
+
+// GGX (Trowbridge–Reitz) microfacet distribution with height-correlated Smith
+// masking-shadowing. Directions are in the local shading frame, the normal along +z.
+#[derive(Debug, Copy, Clone)]
+pub struct Ggx {
+    pub alpha_x: f32,
+    pub alpha_y: f32,
+}
+
+impl Ggx {
+    pub fn new(alpha_x: f32, alpha_y: f32) -> Self {
+        Self { alpha_x: alpha_x.max(MIN_ALPHA), alpha_y: alpha_y.max(MIN_ALPHA) }
+    }
+
+    // Artist-facing roughness is squared into alpha, as in glTF and Disney
+    pub fn from_roughness(roughness: f32) -> Self {
+        Self::anisotropic(roughness, 0.0)
+    }
+
+    // Disney's mapping: anisotropy in [0, 1] stretches highlights along the tangent
+    pub fn anisotropic(roughness: f32, anisotropy: f32) -> Self {
+        let alpha = roughness.clamp(0.0, 1.0).powi(2);
+        let aspect = (1.0 - 0.9 * anisotropy.clamp(0.0, 1.0)).sqrt();
+        Self::new(alpha / aspect, alpha * aspect)
+    }
+
+    pub fn distribution(&self, h: &Vector3D) -> f32 {
+        if h.z <= 0.0 {
+            return 0.0;
+        }
+        let (x, y) = (h.x / self.alpha_x, h.y / self.alpha_y);
+        let denom = x * x + y * y + h.z * h.z;
+        1.0 / (PI * self.alpha_x * self.alpha_y * denom * denom)
+    }
+
+    fn lambda(&self, w: &Vector3D) -> f32 {
+        if w.z == 0.0 {
+            return f32::INFINITY;
+        }
+        let (x, y) = (w.x * self.alpha_x, w.y * self.alpha_y);
+        let tan2 = (x * x + y * y) / (w.z * w.z);
+        ((1.0 + tan2).sqrt() - 1.0) * 0.5
+    }
+
+    pub fn masking(&self, w: &Vector3D) -> f32 {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    pub fn masking_shadowing(&self, wo: &Vector3D, wi: &Vector3D) -> f32 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    // Distribution of normals visible from `wo`, which `sample_visible_normal` draws from
+    pub fn visible_normal_pdf(&self, wo: &Vector3D, h: &Vector3D) -> f32 {
+        if wo.z <= 0.0 {
+            return 0.0;
+        }
+        self.masking(wo) * wo.dot(h).max(0.0) * self.distribution(h) / wo.z
+    }
+
+    // Specular microfacet reflection without its Fresnel factor: D * G / (4 cos_o cos_i)
+    pub fn reflectance(&self, wo: &Vector3D, wi: &Vector3D) -> f32 {
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return 0.0;
+        }
+        let h = (*wo + *wi).normalize();
+        self.distribution(&h) * self.masking_shadowing(wo, wi) / (4.0 * wo.z * wi.z)
+    }
+
+    // Density of `sample_reflection` over incident directions
+    pub fn reflection_pdf(&self, wo: &Vector3D, wi: &Vector3D) -> f32 {
+        if wo.z <= 0.0 || wi.z <= 0.0 {
+            return 0.0;
+        }
+        let h = (*wo + *wi).normalize();
+        self.visible_normal_pdf(wo, &h) / (4.0 * wo.dot(&h))
+    }
+
+    pub fn sample_reflection(&self, wo: &Vector3D, u1: f32, u2: f32) -> Vector3D {
+        let h = self.sample_visible_normal(wo, u1, u2);
+        -wo.reflect(&h)
+    }
+
+    // Heitz 2018, "Sampling the GGX Distribution of Visible Normals"
+    pub fn sample_visible_normal(&self, wo: &Vector3D, u1: f32, u2: f32) -> Vector3D {
+        let vh = Vector3D::new(self.alpha_x * wo.x, self.alpha_y * wo.y, wo.z).normalize();
+        let length_squared = vh.x * vh.x + vh.y * vh.y;
+        let t1 = if length_squared > 0.0 {
+            Vector3D::new(-vh.y, vh.x, 0.0) * (1.0 / length_squared.sqrt())
+        } else {
+            Vector3D::new(1.0, 0.0, 0.0)
+        };
+        let t2 = vh.cross(&t1);
+
+        let r = u1.sqrt();
+        let phi = 2.0 * PI * u2;
+        let p1 = r * phi.cos();
+        let s = 0.5 * (1.0 + vh.z);
+        let p2 = (1.0 - s) * (1.0 - p1 * p1).sqrt() + s * r * phi.sin();
+        let nh = t1 * p1 + t2 * p2 + vh * (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt();
+
+        Vector3D::new(self.alpha_x * nh.x, self.alpha_y * nh.y, nh.z.max(0.0)).normalize()
+    }
+}
@@ -0,0 +1,6 @@
+pub mod material;
+pub mod bump;
+pub mod microfacet;
+pub mod bsdf;
+pub mod thin_film;
+pub mod principled;
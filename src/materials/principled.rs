@@ -0,0 +1,228 @@
+use std::f32::consts::PI;
+use std::sync::Arc;
+use crate::math::vector3d::Vector3D;
+use crate::core::color::Color;
+use crate::textures::texture::Texture;
+use super::bsdf::{bsdf_sample, cosine_hemisphere, Bsdf, BsdfSample};
+use super::microfacet::{fresnel_schlick, Ggx};
+use super::thin_film::ThinFilm;
+
+// Reflectance of the clearcoat layer at normal incidence, a 1.5 index varnish
+const CLEARCOAT_F0: f32 = 0.04;
+
+// Optional lobes layered over the metallic-roughness base. Everything defaults to off,
+// which leaves the plain glTF material.
+#[derive(Clone)]
+pub struct Layers {
+    pub clearcoat: f32,
+    pub clearcoat_roughness: f32,
+    // Retro-reflective grazing highlight of cloth, tinted towards the base colour
+    pub sheen: f32,
+    pub sheen_tint: f32,
+    // Colour of the sheen, looked up like the albedo; replaces the tint when set
+    pub sheen_color: Option<Arc<dyn Texture>>,
+    pub anisotropy: f32,
+    // Flattens the diffuse falloff the way light scattering under the surface does
+    pub subsurface: f32,
+    // Share of the diffuse light let through to the back side
+    pub translucency: f32,
+    pub thin_film: Option<ThinFilm>,
+}
+
+impl Layers {
+    pub fn new() -> Self {
+        Self {
+            clearcoat: 0.0,
+            clearcoat_roughness: 0.1,
+            sheen: 0.0,
+            sheen_tint: 0.5,
+            sheen_color: None,
+            anisotropy: 0.0,
+            subsurface: 0.0,
+            translucency: 0.0,
+            thin_film: None,
+        }
+    }
+
+    pub fn with_clearcoat(mut self, clearcoat: f32, roughness: f32) -> Self {
+        self.clearcoat = clearcoat.clamp(0.0, 1.0);
+        self.clearcoat_roughness = roughness.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_sheen(mut self, sheen: f32, tint: f32) -> Self {
+        self.sheen = sheen.max(0.0);
+        self.sheen_tint = tint.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_sheen_color(mut self, texture: Arc<dyn Texture>) -> Self {
+        self.sheen_color = Some(texture);
+        self
+    }
+
+    pub fn with_anisotropy(mut self, anisotropy: f32) -> Self {
+        self.anisotropy = anisotropy.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_subsurface(mut self, subsurface: f32) -> Self {
+        self.subsurface = subsurface.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_translucency(mut self, translucency: f32) -> Self {
+        self.translucency = translucency.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_thin_film(mut self, thin_film: ThinFilm) -> Self {
+        self.thin_film = Some(thin_film);
+        self
+    }
+}
+
+impl Default for Layers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Disney-style principled BSDF: the metallic-roughness base (optionally anisotropic and
+// iridescent) with sheen, subsurface and translucency on its diffuse part, all under a
+// clearcoat layer that takes its share of the light first.
+pub struct Principled {
+    pub base_color: Color,
+    pub metallic: f32,
+    pub roughness: f32,
+    pub layers: Layers,
+    // Sheen colour before `layers.sheen` scales it
+    pub sheen_color: Color,
+    pub specular: Ggx,
+    pub coat: Ggx,
+}
+
+impl Principled {
+    // The sheen is white tinted towards the base colour by `layers.sheen_tint`
+    pub fn new(base_color: Color, metallic: f32, roughness: f32, layers: Layers) -> Self {
+        let luminance = base_color.luminance();
+        let tint = if luminance > 0.0 { base_color * (1.0 / luminance) } else { Color::new(1.0, 1.0, 1.0) };
+        Self {
+            base_color,
+            metallic: metallic.clamp(0.0, 1.0),
+            roughness: roughness.clamp(0.0, 1.0),
+            specular: Ggx::anisotropic(roughness, layers.anisotropy),
+            coat: Ggx::from_roughness(layers.clearcoat_roughness),
+            sheen_color: Color::new(1.0, 1.0, 1.0).lerp(tint, layers.sheen_tint),
+            layers,
+        }
+    }
+
+    pub fn with_sheen_color(mut self, color: Color) -> Self {
+        self.sheen_color = color;
+        self
+    }
+
+    fn f0(&self) -> Color {
+        Color::new(0.04, 0.04, 0.04).lerp(self.base_color, self.metallic)
+    }
+
+    fn diffuse_color(&self) -> Color {
+        self.base_color * (1.0 - self.metallic)
+    }
+
+    fn specular_fresnel(&self, cos_theta: f32) -> Color {
+        match &self.layers.thin_film {
+            Some(film) => film.reflectance(self.f0(), cos_theta),
+            None => fresnel_schlick(self.f0(), cos_theta),
+        }
+    }
+
+    // Fraction of the light the clearcoat reflects at this angle
+    fn coat_fresnel(&self, cos_theta: f32) -> f32 {
+        let f0 = Color::new(CLEARCOAT_F0, CLEARCOAT_F0, CLEARCOAT_F0);
+        self.layers.clearcoat * fresnel_schlick(f0, cos_theta).r
+    }
+
+    // Odds of sampling the clearcoat, specular, diffuse and transmission lobes
+    fn lobe_probabilities(&self, wo: &Vector3D) -> [f32; 4] {
+        let coat = self.coat_fresnel(wo.z);
+        let below = 1.0 - coat;
+        let diffuse = self.diffuse_color().luminance() * below;
+        let weights = [
+            coat,
+            self.specular_fresnel(wo.z).luminance() * below,
+            diffuse * (1.0 - self.layers.translucency),
+            diffuse * self.layers.translucency,
+        ];
+        let total: f32 = weights.iter().sum();
+        if total <= 0.0 {
+            return [0.0, 1.0, 0.0, 0.0];
+        }
+        weights.map(|weight| weight / total)
+    }
+}
+
+impl Bsdf for Principled {
+    fn eval(&self, wo: &Vector3D, wi: &Vector3D) -> Color {
+        if wo.z <= 0.0 || wi.z == 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+
+        if wi.z < 0.0 {
+            let through = (1.0 - self.coat_fresnel(wo.z)) * (1.0 - self.coat_fresnel(-wi.z));
+            return self.diffuse_color() * (self.layers.translucency * through / PI);
+        }
+
+        let h = (*wo + *wi).normalize();
+        let cos_d = wi.dot(&h);
+        let fresnel = self.specular_fresnel(cos_d);
+        let specular = fresnel * self.specular.reflectance(wo, wi);
+
+        // Disney's subsurface approximation: Hanrahan–Krueger-like flattening of the
+        // Lambertian lobe, stronger for rough surfaces
+        let (fl, fv) = ((1.0 - wi.z).powi(5), (1.0 - wo.z).powi(5));
+        let fss90 = self.roughness * cos_d * cos_d;
+        let fss = (1.0 + (fss90 - 1.0) * fl) * (1.0 + (fss90 - 1.0) * fv);
+        let ss = 1.25 * (fss * (1.0 / (wi.z + wo.z) - 0.5) + 0.5);
+        let shape = 1.0 + (ss - 1.0) * self.layers.subsurface;
+
+        let kd = Color::new(1.0 - fresnel.r, 1.0 - fresnel.g, 1.0 - fresnel.b);
+        let diffuse = kd * self.diffuse_color() * (shape * (1.0 - self.layers.translucency) / PI);
+        let sheen = self.sheen_color * (self.layers.sheen * (1.0 - self.metallic) * (1.0 - cos_d).powi(5));
+
+        let coat = self.coat_fresnel(cos_d) * self.coat.reflectance(wo, wi);
+        let through = (1.0 - self.coat_fresnel(wo.z)) * (1.0 - self.coat_fresnel(wi.z));
+        (specular + diffuse + sheen) * through + Color::new(coat, coat, coat)
+    }
+
+    fn pdf(&self, wo: &Vector3D, wi: &Vector3D) -> f32 {
+        if wo.z <= 0.0 {
+            return 0.0;
+        }
+        let [coat, specular, diffuse, transmission] = self.lobe_probabilities(wo);
+        if wi.z < 0.0 {
+            return transmission * -wi.z / PI;
+        }
+        coat * self.coat.reflection_pdf(wo, wi)
+            + specular * self.specular.reflection_pdf(wo, wi)
+            + diffuse * wi.z.max(0.0) / PI
+    }
+
+    fn sample(&self, wo: &Vector3D, u: f32, u1: f32, u2: f32) -> Option<BsdfSample> {
+        if wo.z <= 0.0 {
+            return None;
+        }
+        let [coat, specular, diffuse, _] = self.lobe_probabilities(wo);
+        let wi = if u < coat {
+            self.coat.sample_reflection(wo, u1, u2)
+        } else if u < coat + specular {
+            self.specular.sample_reflection(wo, u1, u2)
+        } else if u < coat + specular + diffuse {
+            cosine_hemisphere(u1, u2)
+        } else {
+            -cosine_hemisphere(u1, u2)
+        };
+        bsdf_sample(self, wo, wi)
+    }
+}
@@ -0,0 +1,58 @@
+use std::f32::consts::PI;
+use crate::core::color::Color;
+
+// Wavelengths in nanometres standing in for the red, green and blue channels
+const WAVELENGTHS: [f32; 3] = [650.0, 510.0, 475.0];
+
+// Thin dielectric coating such as oil, soap or anodised metal. Light bouncing off its top
+// and bottom interfaces interferes, colouring reflections by thickness and angle.
+#[derive(Debug, Copy, Clone)]
+pub struct ThinFilm {
+    // Nanometres
+    pub thickness: f32,
+    pub ior: f32,
+}
+
+impl ThinFilm {
+    pub fn new(thickness: f32, ior: f32) -> Self {
+        Self { thickness: thickness.max(0.0), ior: ior.max(1.0) }
+    }
+
+    // Reflectance of the film over a substrate reflecting `f0` at normal incidence,
+    // the substrate treated as a dielectric with the matching index of refraction
+    pub fn reflectance(&self, f0: Color, cos_theta: f32) -> Color {
+        let cos_incident = cos_theta.clamp(0.0, 1.0);
+        let sin2_incident = 1.0 - cos_incident * cos_incident;
+        let cos_film = (1.0 - sin2_incident / (self.ior * self.ior)).max(0.0).sqrt();
+        let phase_scale = 4.0 * PI * self.ior * self.thickness * cos_film;
+
+        let channel = |f0: f32, wavelength: f32| {
+            let root = f0.clamp(0.0, 0.99).sqrt();
+            let substrate = (1.0 + root) / (1.0 - root);
+            let cos_substrate = (1.0 - sin2_incident / (substrate * substrate)).max(0.0).sqrt();
+            let (top_s, top_p) = amplitudes(1.0, self.ior, cos_incident, cos_film);
+            let (bottom_s, bottom_p) = amplitudes(self.ior, substrate, cos_film, cos_substrate);
+
+            // Airy summation of the multiple reflections inside the film
+            let cos_phase = (phase_scale / wavelength).cos();
+            let airy = |r12: f32, r23: f32| {
+                let cross = 2.0 * r12 * r23 * cos_phase;
+                (r12 * r12 + r23 * r23 + cross) / (1.0 + r12 * r12 * r23 * r23 + cross)
+            };
+            (0.5 * (airy(top_s, bottom_s) + airy(top_p, bottom_p))).clamp(0.0, 1.0)
+        };
+
+        Color::new(
+            channel(f0.r, WAVELENGTHS[0]),
+            channel(f0.g, WAVELENGTHS[1]),
+            channel(f0.b, WAVELENGTHS[2]),
+        )
+    }
+}
+
+// Fresnel amplitude coefficients for s and p polarised light crossing from n1 into n2
+fn amplitudes(n1: f32, n2: f32, cos1: f32, cos2: f32) -> (f32, f32) {
+    let s = (n1 * cos1 - n2 * cos2) / (n1 * cos1 + n2 * cos2);
+    let p = (n2 * cos1 - n1 * cos2) / (n2 * cos1 + n1 * cos2);
+    (s, p)
+}
@@ -0,0 +1,83 @@
+use crate::math::vector3d::Vector3D;
+use crate::core::ray::Ray;
+
+#[derive(Debug, Copy, Clone)]
+pub struct Aabb {
+    pub min: Vector3D,
+    pub max: Vector3D,
+}
+
+impl Aabb {
+    pub fn new(min: Vector3D, max: Vector3D) -> Self {
+        Self { min, max }
+    }
+
+    // A box containing nothing, neutral for `union` and `include`
+    pub fn empty() -> Self {
+        Self::new(
+            Vector3D::new(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+            Vector3D::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+        )
+    }
+
+    pub fn include(&self, p: &Vector3D) -> Aabb {
+        Aabb::new(
+            Vector3D::new(self.min.x.min(p.x), self.min.y.min(p.y), self.min.z.min(p.z)),
+            Vector3D::new(self.max.x.max(p.x), self.max.y.max(p.y), self.max.z.max(p.z)),
+        )
+    }
+
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        self.include(&other.min).include(&other.max)
+    }
+
+    pub fn centroid(&self) -> Vector3D {
+        (self.min + self.max) * 0.5
+    }
+
+    // 0, 1 or 2 for the x, y or z extent
+    pub fn longest_axis(&self) -> usize {
+        let extent = self.max - self.min;
+        if extent.x >= extent.y && extent.x >= extent.z {
+            0
+        } else if extent.y >= extent.z {
+            1
+        } else {
+            2
+        }
+    }
+
+    // Entry and exit distances of the ray's line through the box, if it crosses it.
+    pub fn intersect(&self, ray: &Ray) -> Option<(f32, f32)> {
+        let mut t_enter = f32::NEG_INFINITY;
+        let mut t_exit = f32::INFINITY;
+
+        let axes = [
+            (ray.origin.x, ray.direction.x, self.min.x, self.max.x),
+            (ray.origin.y, ray.direction.y, self.min.y, self.max.y),
+            (ray.origin.z, ray.direction.z, self.min.z, self.max.z),
+        ];
+
+        for &(origin, direction, min, max) in &axes {
+            if direction.abs() < 1e-12 {
+                if origin < min || origin > max {
+                    return None;
+                }
+                continue;
+            }
+            let inv = 1.0 / direction;
+            let mut t0 = (min - origin) * inv;
+            let mut t1 = (max - origin) * inv;
+            if t0 > t1 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            t_enter = t_enter.max(t0);
+            t_exit = t_exit.min(t1);
+            if t_enter > t_exit {
+                return None;
+            }
+        }
+
+        Some((t_enter, t_exit))
+    }
+}
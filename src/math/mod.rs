@@ -1 +1,7 @@
-pub mod vector3d;
\ No newline at end of file
+pub mod vector3d;
+pub mod aabb;
+pub mod matrix4;
+pub mod transform;
+pub mod quaternion;
+pub mod motion;
+pub mod track;
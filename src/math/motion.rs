@@ -0,0 +1,241 @@
+use crate::math::vector3d::Vector3D;
+use crate::math::matrix4::Matrix4;
+use crate::math::quaternion::Quaternion;
+use crate::math::transform::Transform;
+use crate::math::aabb::Aabb;
+use crate::math::track::{Interpolation, Track};
+
+// Scale below which a key's axis is taken as flattened; dividing by anything smaller
+// overflows
+const MIN_SCALE: f32 = f32::MIN_POSITIVE;
+// Smallest scale an interpolated transform takes, so its inverse stays finite and usable
+const MIN_INTERPOLATED_SCALE: f32 = 1e-6;
+
+// A transform at one point in time, split into scale, then rotation, then translation so
+// it can be interpolated. Shear is lost.
+#[derive(Debug, Copy, Clone)]
+pub struct TransformKey {
+    pub time: f32,
+    pub translation: Vector3D,
+    pub rotation: Quaternion,
+    pub scale: Vector3D,
+}
+
+impl TransformKey {
+    pub fn new(time: f32, transform: &Transform) -> Self {
+        let m = &transform.matrix.m;
+        let translation = Vector3D::new(m[0][3], m[1][3], m[2][3]);
+        let column = |j: usize| Vector3D::new(m[0][j], m[1][j], m[2][j]);
+        let (x, y, z) = (column(0), column(1), column(2));
+        let mut scale = Vector3D::new(x.length(), y.length(), z.length());
+        // A mirroring transform keeps its handedness in the scale
+        if x.cross(&y).dot(&z) < 0.0 {
+            scale.x = -scale.x;
+        }
+
+        // Axes flattened to nothing carry no direction; they are rebuilt square to the
+        // others so the rotation stays a rotation
+        let scales = [scale.x, scale.y, scale.z];
+        let mut axes = [x, y, z].map(Some);
+        for (axis, scale) in axes.iter_mut().zip(scales) {
+            *axis = axis.filter(|_| scale.abs() > MIN_SCALE).map(|axis| axis * (1.0 / scale));
+        }
+        let axes = match axes {
+            [Some(x), Some(y), Some(z)] => [x, y, z],
+            [None, Some(y), Some(z)] => [y.cross(&z), y, z],
+            [Some(x), None, Some(z)] => [x, z.cross(&x), z],
+            [Some(x), Some(y), None] => [x, y, x.cross(&y)],
+            [None, None, None] => [Vector3D::new(1.0, 0.0, 0.0), Vector3D::new(0.0, 1.0, 0.0), Vector3D::new(0.0, 0.0, 1.0)],
+            _ => {
+                let k = axes.iter().position(Option::is_some).unwrap_or(0);
+                let known = axes[k].unwrap_or(Vector3D::new(1.0, 0.0, 0.0));
+                let (next, _) = known.orthonormal_basis();
+                let mut rebuilt = [known; 3];
+                rebuilt[(k + 1) % 3] = next;
+                rebuilt[(k + 2) % 3] = known.cross(&next);
+                rebuilt
+            }
+        };
+
+        let mut rotation = Matrix4::identity();
+        for (j, axis) in axes.iter().enumerate() {
+            rotation.m[0][j] = axis.x;
+            rotation.m[1][j] = axis.y;
+            rotation.m[2][j] = axis.z;
+        }
+        Self { time, translation, rotation: Quaternion::from_matrix(&rotation), scale }
+    }
+
+    // Keys of opposite handedness, or a curve overshooting, interpolate through zero
+    // scale. It is held just off zero, flattening the object to a sheet, and the inverse
+    // is built from the parts, so the transform never turns out singular.
+    pub fn transform(&self) -> Transform {
+        let clamp = |s: f32| if s.abs() >= MIN_INTERPOLATED_SCALE { s } else { MIN_INTERPOLATED_SCALE.copysign(s) };
+        let scale = Vector3D::new(clamp(self.scale.x), clamp(self.scale.y), clamp(self.scale.z));
+        let rotation = self.rotation.normalize().to_matrix();
+        Transform {
+            matrix: Matrix4::translation(self.translation) * rotation * Matrix4::scaling(scale),
+            inverse: Matrix4::scaling(Vector3D::new(1.0 / scale.x, 1.0 / scale.y, 1.0 / scale.z))
+                * rotation.transpose()
+                * Matrix4::translation(-self.translation),
+        }
+    }
+}
+
+// Keyframed transform. Translation and scale follow their tracks; rotation is slerped
+// between keys, eased the same way through a track of the key index. Before the first
+// and after the last key it holds still.
+#[derive(Debug, Clone, Default)]
+pub struct Motion {
+    pub translation: Track<Vector3D>,
+    pub scale: Track<Vector3D>,
+    pub rotations: Vec<Quaternion>,
+    pub progress: Track<f32>,
+}
+
+impl Motion {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_key(self, time: f32, transform: Transform) -> Self {
+        self.with_keyframe(time, transform, Interpolation::Linear)
+    }
+
+    pub fn with_keyframe(mut self, time: f32, transform: Transform, interpolation: Interpolation) -> Self {
+        self.add_key(time, transform, interpolation);
+        self
+    }
+
+    // Adds a key, replacing one already at that time
+    pub fn add_key(&mut self, time: f32, transform: Transform, interpolation: Interpolation) {
+        let key = TransformKey::new(time, &transform);
+        let index = self.translation.keys.partition_point(|k| k.time < time);
+        let replace = self.translation.keys.get(index).is_some_and(|k| k.time == time);
+        self.translation.add_key(time, key.translation, interpolation);
+        self.scale.add_key(time, key.scale, interpolation);
+        if replace {
+            self.rotations[index] = key.rotation;
+        } else {
+            self.rotations.insert(index, key.rotation);
+        }
+        self.progress = Track::new();
+        for (i, key) in self.translation.keys.iter().enumerate() {
+            self.progress.add_key(key.time, i as f32, key.interpolation);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rotations.is_empty()
+    }
+
+    pub fn times(&self) -> impl Iterator<Item = f32> + '_ {
+        self.translation.keys.iter().map(|key| key.time)
+    }
+
+    pub fn key_at(&self, time: f32) -> Option<TransformKey> {
+        let translation = self.translation.value_at(time)?;
+        let scale = self.scale.value_at(time)?;
+        let progress = self.progress.value_at(time)?.clamp(0.0, (self.rotations.len() - 1) as f32);
+        let index = (progress.floor() as usize).min(self.rotations.len() - 1);
+        let rotation = match self.rotations.get(index + 1) {
+            Some(next) => self.rotations[index].slerp(next, progress - index as f32),
+            None => self.rotations[index],
+        };
+        Some(TransformKey { time, translation, rotation, scale })
+    }
+
+    // Identity when there are no keys
+    pub fn at(&self, time: f32) -> Transform {
+        self.key_at(time).map_or_else(Transform::identity, |key| key.transform())
+    }
+
+    // Box around `local` over the whole motion. Each stretch between two keys is bounded
+    // from the convex hulls of its translation and scale curves; where the rotation
+    // changes, the object is taken as a ball able to face any way.
+    pub fn bounds(&self, local: &Aabb) -> Aabb {
+        let keys = self.translation.keys.len();
+        if keys < 2 {
+            return self.at(self.times().next().unwrap_or(0.0)).bounds(local);
+        }
+        let mut result = Aabb::empty();
+        for i in 0..keys - 1 {
+            let offsets = hull(&self.translation.segment_controls(i));
+            let scales = hull(&self.scale.segment_controls(i));
+            // Bezier easing of the key index can overshoot into the neighbouring keys
+            let nearby = &self.rotations[i.saturating_sub(1)..(i + 3).min(keys)];
+            let still = nearby.iter().all(|rotation| rotation.dot(&nearby[0]).abs() > 1.0 - 1e-6);
+
+            let extent = if still {
+                let rotation = nearby[0].to_matrix();
+                let mut extent = Aabb::empty();
+                for corner in corners(local) {
+                    for scale in corners(&scales) {
+                        let scaled = Vector3D::new(corner.x * scale.x, corner.y * scale.y, corner.z * scale.z);
+                        extent = extent.include(&rotation.transform_vector(&scaled));
+                    }
+                }
+                extent
+            } else {
+                let largest = Vector3D::new(
+                    scales.min.x.abs().max(scales.max.x.abs()),
+                    scales.min.y.abs().max(scales.max.y.abs()),
+                    scales.min.z.abs().max(scales.max.z.abs()),
+                );
+                let radius = corners(local)
+                    .iter()
+                    .map(|corner| Vector3D::new(corner.x * largest.x, corner.y * largest.y, corner.z * largest.z).length())
+                    .fold(0.0, f32::max);
+                let reach = Vector3D::new(radius, radius, radius);
+                Aabb::new(-reach, reach)
+            };
+            result = result.union(&Aabb::new(offsets.min + extent.min, offsets.max + extent.max));
+        }
+        result
+    }
+}
+
+fn hull(points: &[Vector3D]) -> Aabb {
+    points.iter().fold(Aabb::empty(), |bounds, point| bounds.include(point))
+}
+
+fn corners(bounds: &Aabb) -> [Vector3D; 8] {
+    let (a, b) = (bounds.min, bounds.max);
+    [
+        Vector3D::new(a.x, a.y, a.z),
+        Vector3D::new(b.x, a.y, a.z),
+        Vector3D::new(a.x, b.y, a.z),
+        Vector3D::new(b.x, b.y, a.z),
+        Vector3D::new(a.x, a.y, b.z),
+        Vector3D::new(b.x, a.y, b.z),
+        Vector3D::new(a.x, b.y, b.z),
+        Vector3D::new(b.x, b.y, b.z),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use super::*;
+    use crate::core::ray::Ray;
+    use crate::objects::instance::Instance;
+    use crate::objects::object::Object;
+    use crate::objects::sphere::Sphere;
+
+    #[test]
+    fn mirrored_and_plain_keys_interpolate_through_zero_scale() {
+        let motion = Motion::new()
+            .with_key(0.0, Transform::scale(Vector3D::new(-1.0, 1.0, 1.0)))
+            .with_key(1.0, Transform::identity());
+        let halfway = motion.at(0.5);
+        for row in halfway.matrix.m.iter().chain(halfway.inverse.m.iter()) {
+            assert!(row.iter().all(|value| value.is_finite()));
+        }
+
+        // Halfway the sphere is flattened to a disc facing x, which a ray along x still hits
+        let sphere = Instance::new(Arc::new(Sphere::new(Vector3D::new(0.0, 0.0, 0.0), 1.0)), Transform::identity()).with_motion(motion);
+        let ray = Ray::new(Vector3D::new(-5.0, 0.2, 0.0), Vector3D::new(1.0, 0.0, 0.0)).with_time(0.5);
+        let hit = sphere.intersect(&ray).expect("the flattened sphere is hit");
+        assert!((hit.t - 5.0).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,128 @@
+use std::ops::{Add, Mul};
+
+// How a value moves from one key to the next
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Interpolation {
+    #[default]
+    Linear,
+    // Cubic Bezier with automatic handles: smooth through the keys, easing in and out at
+    // the first and last key
+    Bezier,
+}
+
+// Values that can be keyed: anything that can be blended with weights
+pub trait Keyable: Copy + Add<Output = Self> + Mul<f32, Output = Self> {}
+
+impl<T: Copy + Add<Output = T> + Mul<f32, Output = T>> Keyable for T {}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Keyframe<T> {
+    pub time: f32,
+    pub value: T,
+    // Interpolation towards the next key
+    pub interpolation: Interpolation,
+}
+
+// A keyed value over time, holding the first and last values outside the keys
+#[derive(Debug, Clone)]
+pub struct Track<T> {
+    pub keys: Vec<Keyframe<T>>,
+}
+
+impl<T: Keyable> Track<T> {
+    pub fn new() -> Self {
+        Self { keys: Vec::new() }
+    }
+
+    // A track that never changes
+    pub fn constant(value: T) -> Self {
+        Self::new().with_key(0.0, value, Interpolation::Linear)
+    }
+
+    pub fn with_key(mut self, time: f32, value: T, interpolation: Interpolation) -> Self {
+        self.add_key(time, value, interpolation);
+        self
+    }
+
+    // Adds a key, replacing one already at that time
+    pub fn add_key(&mut self, time: f32, value: T, interpolation: Interpolation) {
+        let key = Keyframe { time, value, interpolation };
+        match self.keys.binary_search_by(|k| k.time.total_cmp(&time)) {
+            Ok(index) => self.keys[index] = key,
+            Err(index) => self.keys.insert(index, key),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn end_time(&self) -> Option<f32> {
+        self.keys.last().map(|key| key.time)
+    }
+
+    pub fn value_at(&self, time: f32) -> Option<T> {
+        let first = self.keys.first()?;
+        let last = self.keys.last()?;
+        if time <= first.time {
+            return Some(first.value);
+        }
+        if time >= last.time {
+            return Some(last.value);
+        }
+
+        let next = self.keys.partition_point(|key| key.time <= time);
+        let (a, b) = (&self.keys[next - 1], &self.keys[next]);
+        let t = (time - a.time) / (b.time - a.time);
+        Some(match a.interpolation {
+            Interpolation::Linear => a.value * (1.0 - t) + b.value * t,
+            Interpolation::Bezier => {
+                let (handle_a, handle_b) = self.handles(next - 1);
+                bezier(a.value, handle_a, handle_b, b.value, t)
+            }
+        })
+    }
+
+    // Bezier control points of the segment from key `i` to the next; the curve stays
+    // inside their convex hull. A linear segment repeats its end points.
+    pub fn segment_controls(&self, i: usize) -> [T; 4] {
+        let (a, b) = (&self.keys[i], &self.keys[i + 1]);
+        match a.interpolation {
+            Interpolation::Linear => [a.value, a.value, b.value, b.value],
+            Interpolation::Bezier => {
+                let (handle_a, handle_b) = self.handles(i);
+                [a.value, handle_a, handle_b, b.value]
+            }
+        }
+    }
+
+    // Inner control points of the segment starting at key `i`. Each key's handles lie on
+    // the line through its neighbours (Catmull-Rom tangents), a third of the segment
+    // away; end keys get flat handles.
+    fn handles(&self, i: usize) -> (T, T) {
+        let (a, b) = (&self.keys[i], &self.keys[i + 1]);
+        let third = (b.time - a.time) / 3.0;
+        let handle = |key: usize, direction: f32| {
+            let value = self.keys[key].value;
+            if key == 0 || key + 1 == self.keys.len() {
+                return value;
+            }
+            let (before, after) = (&self.keys[key - 1], &self.keys[key + 1]);
+            let k = direction * third / (after.time - before.time);
+            value + after.value * k + before.value * -k
+        };
+        (handle(i, 1.0), handle(i + 1, -1.0))
+    }
+}
+
+impl<T: Keyable> Default for Track<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Cubic Bezier through `p0` and `p3` with control points `p1` and `p2`
+pub fn bezier<T: Keyable>(p0: T, p1: T, p2: T, p3: T, t: f32) -> T {
+    let s = 1.0 - t;
+    p0 * (s * s * s) + p1 * (3.0 * s * s * t) + p2 * (3.0 * s * t * t) + p3 * (t * t * t)
+}
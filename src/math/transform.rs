@@ -0,0 +1,96 @@
+use crate::math::vector3d::Vector3D;
+use crate::math::matrix4::Matrix4;
+use crate::math::aabb::Aabb;
+use crate::core::ray::Ray;
+
+// An affine transform kept together with its inverse.
+#[derive(Debug, Copy, Clone)]
+pub struct Transform {
+    pub matrix: Matrix4,
+    pub inverse: Matrix4,
+}
+
+impl Transform {
+    pub fn new(matrix: Matrix4) -> Self {
+        let inverse = matrix.inverse().expect("Transform matrix is not invertible");
+        Self { matrix, inverse }
+    }
+
+    pub fn identity() -> Self {
+        Self { matrix: Matrix4::identity(), inverse: Matrix4::identity() }
+    }
+
+    pub fn translate(offset: Vector3D) -> Self {
+        Self::new(Matrix4::translation(offset))
+    }
+
+    pub fn scale(factors: Vector3D) -> Self {
+        Self::new(Matrix4::scaling(factors))
+    }
+
+    pub fn uniform_scale(factor: f32) -> Self {
+        Self::scale(Vector3D::new(factor, factor, factor))
+    }
+
+    pub fn rotate(axis: Vector3D, angle: f32) -> Self {
+        Self::new(Matrix4::rotation(axis, angle))
+    }
+
+    // `self` applied first, then `other`
+    pub fn then(&self, other: &Transform) -> Transform {
+        Transform {
+            matrix: other.matrix * self.matrix,
+            inverse: self.inverse * other.inverse,
+        }
+    }
+
+    pub fn inverted(&self) -> Transform {
+        Transform { matrix: self.inverse, inverse: self.matrix }
+    }
+
+    pub fn is_identity(&self) -> bool {
+        self.matrix == Matrix4::identity()
+    }
+
+    pub fn point(&self, p: &Vector3D) -> Vector3D {
+        self.matrix.transform_point(p)
+    }
+
+    pub fn vector(&self, v: &Vector3D) -> Vector3D {
+        self.matrix.transform_vector(v)
+    }
+
+    // Normals transform by the inverse transpose
+    pub fn normal(&self, n: &Vector3D) -> Vector3D {
+        self.inverse.transpose().transform_vector(n).normalize()
+    }
+
+    // Factor scaling a small patch of surface with unit normal `n` (Nanson's formula)
+    pub fn area_scale(&self, n: &Vector3D) -> f32 {
+        let m = &self.matrix.m;
+        let determinant = m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0]);
+        determinant.abs() * self.inverse.transpose().transform_vector(n).length()
+    }
+
+    // The ray in the transform's local space, and the factor converting local
+    // distances back to distances along the original ray.
+    pub fn ray_to_local(&self, ray: &Ray) -> (Ray, f32) {
+        let direction = self.inverse.transform_vector(&ray.direction);
+        let scale = direction.length();
+        (Ray::new(self.inverse.transform_point(&ray.origin), direction).with_time(ray.time), 1.0 / scale)
+    }
+
+    pub fn bounds(&self, local: &Aabb) -> Aabb {
+        let mut result = Aabb::empty();
+        for &x in &[local.min.x, local.max.x] {
+            for &y in &[local.min.y, local.max.y] {
+                for &z in &[local.min.z, local.max.z] {
+                    result = result.include(&self.point(&Vector3D::new(x, y, z)));
+                }
+            }
+        }
+        result
+    }
+}
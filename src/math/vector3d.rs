@@ -32,6 +32,32 @@ impl Vector3D {
         (self.x * self.x + self.y * self.y + self.z * self.z).sqrt()
     }
 
+    // Mirror direction about `normal` (unit length)
+    pub fn reflect(&self, normal: &Vector3D) -> Vector3D {
+        *self - *normal * (2.0 * self.dot(normal))
+    }
+
+    // Two unit vectors completing this (unit) vector into an orthonormal basis
+    pub fn orthonormal_basis(&self) -> (Vector3D, Vector3D) {
+        let helper = if self.x.abs() < 0.9 {
+            Vector3D::new(1.0, 0.0, 0.0)
+        } else {
+            Vector3D::new(0.0, 1.0, 0.0)
+        };
+        let u = (helper - *self * helper.dot(self)).normalize();
+        let v = self.cross(&u);
+        (u, v)
+    }
+
+    // x, y or z for axis 0, 1 or 2
+    pub fn component(&self, axis: usize) -> f32 {
+        match axis {
+            0 => self.x,
+            1 => self.y,
+            _ => self.z,
+        }
+    }
+
     pub fn normalize(&self) -> Vector3D {
         let mag = self.magnitude();
         Vector3D {
@@ -0,0 +1,20 @@
+use crate::core::color::Color;
+
+// Cheap depth cueing: colours fade exponentially towards the fog colour with distance,
+// without any lighting of the fog itself.
+#[derive(Debug, Copy, Clone)]
+pub struct Fog {
+    pub color: Color,
+    pub density: f32,
+}
+
+impl Fog {
+    pub fn new(color: Color, density: f32) -> Self {
+        Self { color, density: density.max(0.0) }
+    }
+
+    pub fn apply(&self, color: Color, distance: f32) -> Color {
+        let visibility = (-self.density * distance).exp();
+        self.color.lerp(color, visibility)
+    }
+}
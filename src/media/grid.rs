@@ -0,0 +1,83 @@
+use crate::math::vector3d::Vector3D;
+use crate::math::aabb::Aabb;
+
+// Densities on a regular lattice filling `bounds`, interpolated trilinearly. Values are
+// stored x fastest, then y, then z.
+pub struct DensityGrid {
+    pub nx: usize,
+    pub ny: usize,
+    pub nz: usize,
+    pub values: Vec<f32>,
+    pub bounds: Aabb,
+    max: f32,
+}
+
+impl DensityGrid {
+    pub fn new(nx: usize, ny: usize, nz: usize, values: Vec<f32>, bounds: Aabb) -> Self {
+        assert!(nx >= 2 && ny >= 2 && nz >= 2, "a density grid needs at least 2 samples per axis");
+        assert_eq!(values.len(), nx * ny * nz, "value count does not match the grid size");
+        let max = values.iter().cloned().fold(0.0, f32::max);
+        Self { nx, ny, nz, values, bounds, max }
+    }
+
+    // Samples `density` at every lattice point
+    pub fn from_fn(nx: usize, ny: usize, nz: usize, bounds: Aabb, density: impl Fn(&Vector3D) -> f32) -> Self {
+        let mut values = Vec::with_capacity(nx * ny * nz);
+        let size = bounds.max - bounds.min;
+        for z in 0..nz {
+            for y in 0..ny {
+                for x in 0..nx {
+                    let point = bounds.min + Vector3D::new(
+                        size.x * x as f32 / (nx - 1) as f32,
+                        size.y * y as f32 / (ny - 1) as f32,
+                        size.z * z as f32 / (nz - 1) as f32,
+                    );
+                    values.push(density(&point).max(0.0));
+                }
+            }
+        }
+        Self::new(nx, ny, nz, values, bounds)
+    }
+
+    pub fn max_density(&self) -> f32 {
+        self.max
+    }
+
+    // Distance between neighbouring samples along the finest axis
+    pub fn spacing(&self) -> f32 {
+        let size = self.bounds.max - self.bounds.min;
+        (size.x / (self.nx - 1) as f32)
+            .min(size.y / (self.ny - 1) as f32)
+            .min(size.z / (self.nz - 1) as f32)
+    }
+
+    fn value(&self, x: usize, y: usize, z: usize) -> f32 {
+        self.values[(z * self.ny + y) * self.nx + x]
+    }
+
+    pub fn density(&self, point: &Vector3D) -> f32 {
+        let size = self.bounds.max - self.bounds.min;
+        let local = *point - self.bounds.min;
+        let coordinate = |offset: f32, extent: f32, count: usize| {
+            let scaled = offset / extent * (count - 1) as f32;
+            if !(0.0..=(count - 1) as f32).contains(&scaled) {
+                return None;
+            }
+            let cell = (scaled.floor() as usize).min(count - 2);
+            Some((cell, scaled - cell as f32))
+        };
+        let (x, fx) = match coordinate(local.x, size.x, self.nx) { Some(c) => c, None => return 0.0 };
+        let (y, fy) = match coordinate(local.y, size.y, self.ny) { Some(c) => c, None => return 0.0 };
+        let (z, fz) = match coordinate(local.z, size.z, self.nz) { Some(c) => c, None => return 0.0 };
+
+        let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+        let plane = |z: usize| {
+            lerp(
+                lerp(self.value(x, y, z), self.value(x + 1, y, z), fx),
+                lerp(self.value(x, y + 1, z), self.value(x + 1, y + 1, z), fx),
+                fy,
+            )
+        };
+        lerp(plane(z), plane(z + 1), fz)
+    }
+}
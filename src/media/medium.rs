@@ -0,0 +1,161 @@
+use crate::math::vector3d::Vector3D;
+use crate::math::aabb::Aabb;
+use crate::core::color::Color;
+use crate::core::ray::Ray;
+use crate::core::rng::Rng;
+use super::grid::DensityGrid;
+use super::phase::HenyeyGreenstein;
+
+// Steps used to integrate density along a ray through a grid, per grid sample spacing
+const STEPS_PER_VOXEL: f32 = 1.0;
+
+pub enum Density {
+    Uniform,
+    Grid(DensityGrid),
+}
+
+// Participating medium filling `bounds` (everything when None). Absorption and
+// scattering are per unit length at density 1; `color` tints the scattered light.
+pub struct Medium {
+    pub absorption: f32,
+    pub scattering: f32,
+    pub color: Color,
+    pub phase: HenyeyGreenstein,
+    pub density: Density,
+    pub bounds: Option<Aabb>,
+}
+
+impl Medium {
+    pub fn homogeneous(absorption: f32, scattering: f32) -> Self {
+        Self {
+            absorption: absorption.max(0.0),
+            scattering: scattering.max(0.0),
+            color: Color::new(1.0, 1.0, 1.0),
+            phase: HenyeyGreenstein::default(),
+            density: Density::Uniform,
+            bounds: None,
+        }
+    }
+
+    pub fn heterogeneous(grid: DensityGrid, absorption: f32, scattering: f32) -> Self {
+        let bounds = grid.bounds;
+        Self { density: Density::Grid(grid), bounds: Some(bounds), ..Self::homogeneous(absorption, scattering) }
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_anisotropy(mut self, g: f32) -> Self {
+        self.phase = HenyeyGreenstein::new(g);
+        self
+    }
+
+    pub fn with_bounds(mut self, bounds: Aabb) -> Self {
+        self.bounds = Some(bounds);
+        self
+    }
+
+    pub fn extinction(&self) -> f32 {
+        self.absorption + self.scattering
+    }
+
+    // Chance that a collision scatters the light rather than absorbing it
+    pub fn albedo(&self) -> f32 {
+        let extinction = self.extinction();
+        if extinction > 0.0 { self.scattering / extinction } else { 0.0 }
+    }
+
+    pub fn density_at(&self, point: &Vector3D) -> f32 {
+        if let Some(bounds) = &self.bounds {
+            let inside = (0..3).all(|axis| {
+                let c = point.component(axis);
+                c >= bounds.min.component(axis) && c <= bounds.max.component(axis)
+            });
+            if !inside {
+                return 0.0;
+            }
+        }
+        match &self.density {
+            Density::Uniform => 1.0,
+            Density::Grid(grid) => grid.density(point),
+        }
+    }
+
+    fn max_density(&self) -> f32 {
+        match &self.density {
+            Density::Uniform => 1.0,
+            Density::Grid(grid) => grid.max_density(),
+        }
+    }
+
+    // Stretch of the ray, up to `max_t`, inside the medium's bounds
+    pub fn segment(&self, ray: &Ray, max_t: f32) -> Option<(f32, f32)> {
+        let (enter, exit) = match &self.bounds {
+            Some(bounds) => bounds.intersect(ray)?,
+            None => (0.0, f32::INFINITY),
+        };
+        let (enter, exit) = (enter.max(0.0), exit.min(max_t));
+        if enter < exit { Some((enter, exit)) } else { None }
+    }
+
+    // Extinction integrated along the ray up to `max_t`
+    pub fn optical_depth(&self, ray: &Ray, max_t: f32) -> f32 {
+        let (enter, exit) = match self.segment(ray, max_t) {
+            Some(segment) => segment,
+            None => return 0.0,
+        };
+        match &self.density {
+            Density::Uniform => {
+                if exit.is_infinite() {
+                    return if self.extinction() > 0.0 { f32::INFINITY } else { 0.0 };
+                }
+                self.extinction() * (exit - enter)
+            }
+            Density::Grid(grid) => {
+                // Midpoint rule at about one step per voxel
+                let length = exit - enter;
+                let steps = ((length / grid.spacing() * STEPS_PER_VOXEL).ceil() as usize).clamp(1, 1024);
+                let step = length / steps as f32;
+                let sum: f32 = (0..steps)
+                    .map(|i| grid.density(&ray.point_at(enter + (i as f32 + 0.5) * step)))
+                    .sum();
+                self.extinction() * sum * step
+            }
+        }
+    }
+
+    pub fn transmittance(&self, ray: &Ray, max_t: f32) -> f32 {
+        (-self.optical_depth(ray, max_t)).exp()
+    }
+
+    // Distance to the first collision with the medium before `max_t`, by delta tracking
+    // against the largest density
+    pub fn sample_collision(&self, ray: &Ray, max_t: f32, rng: &mut Rng) -> Option<f32> {
+        let (enter, exit) = self.segment(ray, max_t)?;
+        let majorant = self.extinction() * self.max_density();
+        if majorant <= 0.0 {
+            return None;
+        }
+
+        let mut t = enter;
+        loop {
+            t -= (1.0 - rng.next_f32()).ln() / majorant;
+            if t >= exit {
+                return None;
+            }
+            if let Density::Uniform = self.density {
+                return Some(t);
+            }
+            if rng.next_f32() * self.max_density() < self.density_at(&ray.point_at(t)) {
+                return Some(t);
+            }
+        }
+    }
+}
+
+// Fraction of light crossing all media along the ray up to `max_t`
+pub fn transmittance(media: &[Medium], ray: &Ray, max_t: f32) -> f32 {
+    media.iter().map(|medium| medium.transmittance(ray, max_t)).product()
+}
@@ -0,0 +1,4 @@
+pub mod fog;
+pub mod phase;
+pub mod grid;
+pub mod medium;
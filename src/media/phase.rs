@@ -0,0 +1,45 @@
+use std::f32::consts::PI;
+use crate::math::vector3d::Vector3D;
+
+// Henyey–Greenstein phase function. `g` in (-1, 1): positive scatters forwards, as haze
+// and smoke do, negative backwards, 0 evenly in all directions.
+#[derive(Debug, Copy, Clone)]
+pub struct HenyeyGreenstein {
+    pub g: f32,
+}
+
+impl HenyeyGreenstein {
+    pub fn new(g: f32) -> Self {
+        Self { g: g.clamp(-0.99, 0.99) }
+    }
+
+    // Density of turning from travelling along `incoming` to travelling along `outgoing`
+    pub fn eval(&self, incoming: &Vector3D, outgoing: &Vector3D) -> f32 {
+        let cos_theta = incoming.dot(outgoing);
+        let g2 = self.g * self.g;
+        let denom = 1.0 + g2 - 2.0 * self.g * cos_theta;
+        (1.0 - g2) / (4.0 * PI * denom * denom.sqrt())
+    }
+
+    // New direction of travel for light arriving along `incoming`; sampled exactly, so
+    // its pdf equals `eval`
+    pub fn sample(&self, incoming: &Vector3D, u1: f32, u2: f32) -> Vector3D {
+        let g = self.g;
+        let cos_theta = if g.abs() < 1e-3 {
+            1.0 - 2.0 * u1
+        } else {
+            let square = (1.0 - g * g) / (1.0 - g + 2.0 * g * u1);
+            (1.0 + g * g - square * square) / (2.0 * g)
+        };
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * PI * u2;
+        let (tangent, bitangent) = incoming.orthonormal_basis();
+        (tangent * (sin_theta * phi.cos()) + bitangent * (sin_theta * phi.sin()) + *incoming * cos_theta).normalize()
+    }
+}
+
+impl Default for HenyeyGreenstein {
+    fn default() -> Self {
+        Self::new(0.0)
+    }
+}
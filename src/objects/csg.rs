@@ -0,0 +1,128 @@
+use crate::math::vector3d::Vector3D;
+use crate::math::aabb::Aabb;
+use crate::core::ray::Ray;
+use super::object::{Object, Intersection, Span};
+
+// Hits closer than this are treated as the surface the ray starts on.
+const EPSILON: f32 = 1e-4;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CsgOperation {
+    Union,
+    Intersection,
+    Difference,
+}
+
+impl CsgOperation {
+    fn contains(&self, in_left: bool, in_right: bool) -> bool {
+        match self {
+            CsgOperation::Union => in_left || in_right,
+            CsgOperation::Intersection => in_left && in_right,
+            CsgOperation::Difference => in_left && !in_right,
+        }
+    }
+
+    // Merges the sorted spans of two operands into the spans of the combined solid.
+    pub fn combine(&self, left: Vec<Span>, right: Vec<Span>) -> Vec<Span> {
+        let mut events: Vec<(Intersection, bool)> = Vec::new();
+        for span in left {
+            events.push((span.enter, true));
+            events.push((span.exit, true));
+        }
+        for span in right {
+            events.push((span.enter, false));
+            events.push((span.exit, false));
+        }
+        events.sort_by(|a, b| a.0.t.partial_cmp(&b.0.t).unwrap_or(std::cmp::Ordering::Equal));
+
+        let mut spans = Vec::new();
+        let mut in_left = false;
+        let mut in_right = false;
+        let mut enter: Option<Intersection> = None;
+
+        for (mut boundary, from_left) in events {
+            let was_inside = self.contains(in_left, in_right);
+            if from_left {
+                in_left = !in_left;
+            } else {
+                in_right = !in_right;
+            }
+            let is_inside = self.contains(in_left, in_right);
+
+            // The subtracted operand's surfaces face into the result
+            if !from_left && *self == CsgOperation::Difference {
+                boundary.normal = -boundary.normal;
+            }
+
+            if !was_inside && is_inside {
+                enter = Some(boundary);
+            } else if was_inside && !is_inside {
+                if let Some(enter) = enter.take() {
+                    spans.push(Span { enter, exit: boundary });
+                }
+            }
+        }
+
+        spans
+    }
+}
+
+pub struct Csg {
+    pub operation: CsgOperation,
+    pub left: Box<dyn Object>,
+    pub right: Box<dyn Object>,
+}
+
+impl Csg {
+    pub fn new(operation: CsgOperation, left: Box<dyn Object>, right: Box<dyn Object>) -> Self {
+        Self { operation, left, right }
+    }
+
+    pub fn union(left: Box<dyn Object>, right: Box<dyn Object>) -> Self {
+        Self::new(CsgOperation::Union, left, right)
+    }
+
+    pub fn intersection(left: Box<dyn Object>, right: Box<dyn Object>) -> Self {
+        Self::new(CsgOperation::Intersection, left, right)
+    }
+
+    pub fn difference(left: Box<dyn Object>, right: Box<dyn Object>) -> Self {
+        Self::new(CsgOperation::Difference, left, right)
+    }
+}
+
+impl Object for Csg {
+    fn intersect(&self, ray: &Ray) -> Option<Intersection> {
+        for span in self.spans(ray) {
+            for boundary in [span.enter, span.exit] {
+                if boundary.t > EPSILON && boundary.t.is_finite() {
+                    return Some(boundary);
+                }
+            }
+        }
+        None
+    }
+
+    // The renderer uses the normal carried by the intersection; without knowing which
+    // operand a point came from, this falls back to the left operand's surface.
+    fn normal(&self, point: &Vector3D) -> Vector3D {
+        self.left.normal(point)
+    }
+
+    fn normal_at(&self, point: &Vector3D, time: f32) -> Vector3D {
+        self.left.normal_at(point, time)
+    }
+
+    fn spans(&self, ray: &Ray) -> Vec<Span> {
+        self.operation.combine(self.left.spans(ray), self.right.spans(ray))
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        let (left, right) = (self.left.bounds(), self.right.bounds());
+        match self.operation {
+            CsgOperation::Union => Some(left?.union(&right?)),
+            CsgOperation::Intersection => left.or(right),
+            CsgOperation::Difference => left,
+        }
+    }
+}
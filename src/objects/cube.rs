@@ -1,6 +1,7 @@
 use crate::math::vector3d::Vector3D;
+use crate::math::aabb::Aabb;
 use crate::core::ray::Ray;
-use super::object::{Object, Intersection};
+use super::object::{Object, Intersection, Span};
 
 pub struct Cube {
     pub min: Vector3D,
@@ -11,10 +12,37 @@ impl Cube {
     pub fn new(min: Vector3D, max: Vector3D) -> Self {
         Self { min, max }
     }
-}
 
-impl Object for Cube {
-    fn intersect(&self, ray: &Ray) -> Option<Intersection> {
+    // Each face maps the full [0, 1] square
+    fn uv(&self, point: &Vector3D, normal: &Vector3D) -> (f32, f32) {
+        let extent = self.max - self.min;
+        let q = *point - self.min;
+        let (x, y, z) = (q.x / extent.x, q.y / extent.y, q.z / extent.z);
+        if normal.x != 0.0 {
+            (z, y)
+        } else if normal.y != 0.0 {
+            (x, z)
+        } else {
+            (x, y)
+        }
+    }
+
+    fn tangents(&self, normal: &Vector3D) -> (Vector3D, Vector3D) {
+        let extent = self.max - self.min;
+        let x = Vector3D::new(extent.x, 0.0, 0.0);
+        let y = Vector3D::new(0.0, extent.y, 0.0);
+        let z = Vector3D::new(0.0, 0.0, extent.z);
+        if normal.x != 0.0 {
+            (z, y)
+        } else if normal.y != 0.0 {
+            (x, z)
+        } else {
+            (x, y)
+        }
+    }
+
+    // Entry and exit distances of the ray's line through the box.
+    fn slab(&self, ray: &Ray) -> Option<(f32, f32)> {
         let mut tmin = (self.min.x - ray.origin.x) / ray.direction.x;
         let mut tmax = (self.max.x - ray.origin.x) / ray.direction.x;
 
@@ -60,6 +88,14 @@ impl Object for Cube {
             tmax = tzmax;
         }
 
+        Some((tmin, tmax))
+    }
+}
+
+impl Object for Cube {
+    fn intersect(&self, ray: &Ray) -> Option<Intersection> {
+        let (mut tmin, tmax) = self.slab(ray)?;
+
         if tmin < 0.0 {
             tmin = tmax;
             if tmin < 0.0 {
@@ -69,7 +105,9 @@ impl Object for Cube {
 
         let point = ray.point_at(tmin);
         let normal = self.normal(&point);
-        Some(Intersection { t: tmin, point, normal })
+        let (u, v) = self.uv(&point, &normal);
+        let (tangent, bitangent) = self.tangents(&normal);
+        Some(Intersection::new(tmin, point, normal).with_uv(u, v).with_tangents(tangent, bitangent))
     }
 
     fn normal(&self, point: &Vector3D) -> Vector3D {
@@ -85,4 +123,24 @@ impl Object for Cube {
             Vector3D::new(0.0, 0.0, d.z.signum() * bias)
         }
     }
-}
\ No newline at end of file
+
+    fn spans(&self, ray: &Ray) -> Vec<Span> {
+        match self.slab(ray) {
+            Some((tmin, tmax)) => {
+                let hit = |t: f32| {
+                    let point = ray.point_at(t);
+                    let normal = self.normal(&point);
+                    let (u, v) = self.uv(&point, &normal);
+                    let (tangent, bitangent) = self.tangents(&normal);
+                    Intersection::new(t, point, normal).with_uv(u, v).with_tangents(tangent, bitangent)
+                };
+                vec![Span { enter: hit(tmin), exit: hit(tmax) }]
+            }
+            None => Vec::new(),
+        }
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        Some(Aabb::new(self.min, self.max))
+    }
+}
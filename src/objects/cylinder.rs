@@ -1,12 +1,18 @@
 use crate::math::vector3d::Vector3D;
+use crate::math::aabb::Aabb;
 use crate::core::ray::Ray;
-use super::object::{Object, Intersection};
+use super::object::{Object, Intersection, Span};
+use super::csg::CsgOperation;
 
 pub struct Cylinder {
     pub center: Vector3D,
     pub axis: Vector3D,
     pub radius: f32,
     pub height: f32,
+    pub inner_radius: f32,
+    pub cap_bottom: bool,
+    pub cap_top: bool,
+    pub sweep: Option<(f32, f32)>,
 }
 
 impl Cylinder {
@@ -16,8 +22,171 @@ impl Cylinder {
             axis: axis.normalize(),
             radius,
             height,
+            inner_radius: 0.0,
+            cap_bottom: true,
+            cap_top: true,
+            sweep: None,
         }
     }
+
+    // Tube ouvert : aucune des deux bases n'est rendue
+    pub fn open(self) -> Self {
+        self.with_caps(false, false)
+    }
+
+    pub fn with_caps(mut self, bottom: bool, top: bool) -> Self {
+        self.cap_bottom = bottom;
+        self.cap_top = top;
+        self
+    }
+
+    // Cylindre creux : les bases deviennent des anneaux et la paroi intérieure est rendue
+    pub fn with_inner_radius(mut self, inner_radius: f32) -> Self {
+        self.inner_radius = inner_radius.max(0.0).min(self.radius);
+        self
+    }
+
+    // Balayage partiel autour de l'axe, angles en degrés
+    pub fn with_sweep(mut self, start: f32, end: f32) -> Self {
+        self.sweep = Some((start, end));
+        self
+    }
+
+    // Une surface sans épaisseur est visible des deux côtés. Un cylindre balayé reste
+    // fermé tant que ses coupes le sont (voir `cut_faces`).
+    fn is_shell(&self) -> bool {
+        self.inner_radius <= 0.0 && (!self.cap_bottom || !self.cap_top)
+    }
+
+    // Angles de début et de fin quand le balayage ne fait pas le tour complet
+    fn partial_sweep(&self) -> Option<(f32, f32)> {
+        self.sweep.filter(|(start, end)| end - start < 360.0)
+    }
+
+    // Faces planes fermant la coupe d'un balayage partiel, du rayon intérieur au rayon
+    // extérieur, quand le cylindre a une épaisseur ou ses deux bases. Chaque face est
+    // donnée par sa direction radiale et sa normale sortante.
+    fn cut_faces(&self) -> Vec<(Vector3D, Vector3D)> {
+        let (start, end) = match self.partial_sweep() {
+            Some(sweep) if self.inner_radius > 0.0 || (self.cap_bottom && self.cap_top) => sweep,
+            _ => return Vec::new(),
+        };
+        let (u, v) = self.angle_basis();
+        let radial = |angle: f32| u * angle.to_radians().cos() + v * angle.to_radians().sin();
+        let tangent = |angle: f32| u * -angle.to_radians().sin() + v * angle.to_radians().cos();
+        vec![(radial(start), -tangent(start)), (radial(end), tangent(end))]
+    }
+
+    fn angle_basis(&self) -> (Vector3D, Vector3D) {
+        self.axis.orthonormal_basis()
+    }
+
+    // Paroi : u suit l'angle autour de l'axe, v la hauteur. Bases : projection du disque.
+    fn uv(&self, point: &Vector3D, on_wall: bool) -> (f32, f32) {
+        let (u_axis, v_axis) = self.angle_basis();
+        let d = *point - self.center;
+        if on_wall {
+            let angle = d.dot(&v_axis).atan2(d.dot(&u_axis));
+            let u = (angle / (2.0 * std::f32::consts::PI)).rem_euclid(1.0);
+            (u, d.dot(&self.axis) / self.height)
+        } else {
+            let diameter = 2.0 * self.radius;
+            (d.dot(&u_axis) / diameter + 0.5, d.dot(&v_axis) / diameter + 0.5)
+        }
+    }
+
+    fn tangents(&self, point: &Vector3D, on_wall: bool) -> (Vector3D, Vector3D) {
+        let (u_axis, v_axis) = self.angle_basis();
+        if on_wall {
+            let d = *point - self.center;
+            let radial = d - self.axis * d.dot(&self.axis);
+            let tangent = self.axis.cross(&radial) * (2.0 * std::f32::consts::PI);
+            (tangent, self.axis * self.height)
+        } else {
+            let diameter = 2.0 * self.radius;
+            (u_axis * diameter, v_axis * diameter)
+        }
+    }
+
+    // Intervalles du cylindre plein de rayon donné, bases comprises
+    fn solid_spans(&self, ray: &Ray, radius: f32) -> Vec<Span> {
+        let oc = ray.origin - self.center;
+        let axis_dot_dir = ray.direction.dot(&self.axis);
+        let axis_dot_oc = oc.dot(&self.axis);
+        let everywhere = (f32::NEG_INFINITY, f32::INFINITY);
+
+        // Intervalle à l'intérieur de la surface latérale infinie
+        let a = ray.direction.dot(&ray.direction) - axis_dot_dir.powi(2);
+        let c = oc.dot(&oc) - axis_dot_oc.powi(2) - radius.powi(2);
+        let lateral = if a.abs() < 1e-9 {
+            if c >= 0.0 {
+                return Vec::new();
+            }
+            everywhere
+        } else {
+            let b = 2.0 * (ray.direction.dot(&oc) - axis_dot_dir * axis_dot_oc);
+            let discriminant = b * b - 4.0 * a * c;
+            if discriminant < 0.0 {
+                return Vec::new();
+            }
+            let sqrt_discriminant = discriminant.sqrt();
+            ((-b - sqrt_discriminant) / (2.0 * a), (-b + sqrt_discriminant) / (2.0 * a))
+        };
+
+        // Intervalle entre les plans des deux bases
+        let between_caps = if axis_dot_dir.abs() < 1e-9 {
+            if axis_dot_oc < 0.0 || axis_dot_oc > self.height {
+                return Vec::new();
+            }
+            everywhere
+        } else {
+            let t_bottom = -axis_dot_oc / axis_dot_dir;
+            let t_top = (self.height - axis_dot_oc) / axis_dot_dir;
+            (t_bottom.min(t_top), t_bottom.max(t_top))
+        };
+
+        let t_enter = lateral.0.max(between_caps.0);
+        let t_exit = lateral.1.min(between_caps.1);
+        if t_enter > t_exit {
+            return Vec::new();
+        }
+
+        let boundary = |t: f32, on_lateral: bool| {
+            let point = ray.point_at(t);
+            let height_check = (point - self.center).dot(&self.axis);
+            let normal = if on_lateral {
+                (point - (self.center + self.axis * height_check)).normalize()
+            } else if height_check < self.height * 0.5 {
+                -self.axis
+            } else {
+                self.axis
+            };
+            let (u, v) = self.uv(&point, on_lateral);
+            let (tangent, bitangent) = self.tangents(&point, on_lateral);
+            Intersection::new(t, point, normal).with_uv(u, v).with_tangents(tangent, bitangent)
+        };
+
+        vec![Span {
+            enter: boundary(t_enter, lateral.0 >= between_caps.0),
+            exit: boundary(t_exit, lateral.1 <= between_caps.1),
+        }]
+    }
+
+    fn in_sweep(&self, point: &Vector3D) -> bool {
+        let (start, end) = match self.sweep {
+            Some(sweep) => sweep,
+            None => return true,
+        };
+        if end - start >= 360.0 {
+            return true;
+        }
+
+        let (u, v) = self.angle_basis();
+        let d = *point - self.center;
+        let angle = d.dot(&v).atan2(d.dot(&u)).to_degrees();
+        let offset = (angle - start).rem_euclid(360.0);
+        offset <= (end - start).rem_euclid(360.0)
+    }
 }
 
 impl Object for Cylinder {
@@ -29,16 +198,26 @@ impl Object for Cylinder {
         // Calcul des coefficients quadratiques pour la surface latérale
         let a = ray.direction.dot(&ray.direction) - axis_dot_dir.powi(2);
         let b = 2.0 * (ray.direction.dot(&oc) - axis_dot_dir * axis_dot_oc);
-        let c = oc.dot(&oc) - axis_dot_oc.powi(2) - self.radius.powi(2);
-
-        let discriminant = b * b - 4.0 * a * c;
 
         let mut closest_t = f32::INFINITY;
         let mut closest_point = Vector3D::new(0.0, 0.0, 0.0);
         let mut closest_normal = Vector3D::new(0.0, 0.0, 0.0);
+        let mut closest_on_wall = true;
+
+        // Vérification de l'intersection avec les parois extérieure et intérieure
+        let walls = [(self.radius, 1.0), (self.inner_radius, -1.0)];
+
+        for &(radius, side) in &walls {
+            if radius <= 0.0 {
+                continue;
+            }
+
+            let c = oc.dot(&oc) - axis_dot_oc.powi(2) - radius.powi(2);
+            let discriminant = b * b - 4.0 * a * c;
+            if discriminant < 0.0 {
+                continue;
+            }
 
-        // Vérification de l'intersection avec la surface latérale
-        if discriminant >= 0.0 {
             let sqrt_discriminant = discriminant.sqrt();
             let t1 = (-b - sqrt_discriminant) / (2.0 * a);
             let t2 = (-b + sqrt_discriminant) / (2.0 * a);
@@ -47,45 +226,108 @@ impl Object for Cylinder {
                 if t > 0.0 && t < closest_t {
                     let point = ray.point_at(t);
                     let height_check = (point - self.center).dot(&self.axis);
-                    if height_check >= 0.0 && height_check <= self.height {
+                    if height_check >= 0.0 && height_check <= self.height && self.in_sweep(&point) {
                         closest_t = t;
                         closest_point = point;
-                        closest_normal = (point - (self.center + self.axis * height_check)).normalize();
+                        // La normale de la paroi intérieure pointe vers l'axe
+                        closest_normal = (point - (self.center + self.axis * height_check)).normalize() * side;
+                        closest_on_wall = true;
                     }
                 }
             }
         }
 
         // Vérification de l'intersection avec les bases
-        let base_centers = [self.center, self.center + self.axis * self.height];
-        let base_normals = [-self.axis, self.axis];
+        let caps = [
+            (self.cap_bottom, self.center, -self.axis),
+            (self.cap_top, self.center + self.axis * self.height, self.axis),
+        ];
 
-        for (&base_center, &base_normal) in base_centers.iter().zip(base_normals.iter()) {
+        for &(enabled, base_center, base_normal) in &caps {
+            if !enabled {
+                continue;
+            }
             let t = (base_center - ray.origin).dot(&base_normal) / ray.direction.dot(&base_normal);
             if t > 0.0 && t < closest_t {
                 let point = ray.point_at(t);
-                if (point - base_center).magnitude() <= self.radius {
+                let distance = (point - base_center).magnitude();
+                if distance <= self.radius && distance >= self.inner_radius && self.in_sweep(&point) {
                     closest_t = t;
                     closest_point = point;
                     closest_normal = base_normal;
+                    closest_on_wall = false;
                 }
             }
         }
 
-        if closest_t != f32::INFINITY {
-            Some(Intersection { t: closest_t, point: closest_point, normal: closest_normal })
-        } else {
-            None
+        // Vérification de l'intersection avec les faces de la coupe
+        let mut cut_uv = None;
+        for (radial, face_normal) in self.cut_faces() {
+            let t = (self.center - ray.origin).dot(&face_normal) / ray.direction.dot(&face_normal);
+            if t > 0.0 && t < closest_t {
+                let point = ray.point_at(t);
+                let d = point - self.center;
+                let (height, distance) = (d.dot(&self.axis), d.dot(&radial));
+                if height >= 0.0 && height <= self.height && distance >= self.inner_radius && distance <= self.radius {
+                    closest_t = t;
+                    closest_point = point;
+                    closest_normal = face_normal;
+                    cut_uv = Some(((distance / self.radius, height / self.height), (radial * self.radius, self.axis * self.height)));
+                }
+            }
+        }
+
+        if closest_t == f32::INFINITY {
+            return None;
+        }
+
+        if self.is_shell() && closest_normal.dot(&ray.direction) > 0.0 {
+            closest_normal = -closest_normal;
         }
+
+        // Sur une coupe : u suit le rayon, v la hauteur
+        let ((u, v), (tangent, bitangent)) = cut_uv.unwrap_or_else(|| {
+            (self.uv(&closest_point, closest_on_wall), self.tangents(&closest_point, closest_on_wall))
+        });
+        Some(Intersection::new(closest_t, closest_point, closest_normal).with_uv(u, v).with_tangents(tangent, bitangent))
     }
 
     fn normal(&self, point: &Vector3D) -> Vector3D {
         let height_check = (*point - self.center).dot(&self.axis);
-        if height_check.abs() < 1e-6 {
+        if self.cap_bottom && height_check.abs() < 1e-6 {
             return -self.axis;
-        } else if (height_check - self.height).abs() < 1e-6 {
+        } else if self.cap_top && (height_check - self.height).abs() < 1e-6 {
             return self.axis;
         }
-        (*point - (self.center + self.axis * height_check)).normalize()
+
+        let radial = *point - (self.center + self.axis * height_check);
+        if self.inner_radius > 0.0
+            && (radial.magnitude() - self.inner_radius).abs() < (radial.magnitude() - self.radius).abs()
+        {
+            return -radial.normalize();
+        }
+        radial.normalize()
     }
-}
\ No newline at end of file
+
+    // En CSG le cylindre est toujours traité comme un solide fermé ; seul le rayon
+    // intérieur est pris en compte, les bases ouvertes et le balayage sont ignorés.
+    fn spans(&self, ray: &Ray) -> Vec<Span> {
+        let outer = self.solid_spans(ray, self.radius);
+        if self.inner_radius <= 0.0 {
+            return outer;
+        }
+        CsgOperation::Difference.combine(outer, self.solid_spans(ray, self.inner_radius))
+    }
+
+    // Boîte englobant les deux disques des bases
+    fn bounds(&self) -> Option<Aabb> {
+        let top = self.center + self.axis * self.height;
+        let extent = Vector3D::new(
+            self.radius * (1.0 - self.axis.x * self.axis.x).max(0.0).sqrt(),
+            self.radius * (1.0 - self.axis.y * self.axis.y).max(0.0).sqrt(),
+            self.radius * (1.0 - self.axis.z * self.axis.z).max(0.0).sqrt(),
+        );
+        let bounds = Aabb::empty().include(&self.center).include(&top);
+        Some(Aabb::new(bounds.min - extent, bounds.max + extent))
+    }
+}
@@ -0,0 +1,183 @@
+use crate::math::vector3d::Vector3D;
+use crate::math::aabb::Aabb;
+use crate::core::ray::Ray;
+use super::object::{Object, Intersection};
+use image::error::{ParameterError, ParameterErrorKind};
+use image::ImageError;
+
+// Terrain over a regular grid of height samples, spanning `size` from the `origin` corner.
+// Samples are normalized to [0, 1] and scaled by `size.y`.
+pub struct Heightfield {
+    pub origin: Vector3D,
+    pub size: Vector3D,
+    pub columns: usize,
+    pub rows: usize,
+    heights: Vec<f32>,
+}
+
+impl Heightfield {
+    pub fn new(origin: Vector3D, size: Vector3D, columns: usize, rows: usize, heights: Vec<f32>) -> Self {
+        assert!(columns >= 2 && rows >= 2, "a heightfield needs at least 2x2 samples");
+        assert_eq!(heights.len(), columns * rows, "height sample count does not match the grid");
+        Self { origin, size, columns, rows, heights }
+    }
+
+    // Grayscale image as height map: black is `origin.y`, white is `origin.y + size.y`.
+    // Images smaller than 2x2 are rejected.
+    pub fn from_image(path: &str, origin: Vector3D, size: Vector3D) -> Result<Self, ImageError> {
+        let image = image::open(path)?.to_luma8();
+        let (columns, rows) = (image.width() as usize, image.height() as usize);
+        if columns < 2 || rows < 2 {
+            return Err(ImageError::Parameter(ParameterError::from_kind(ParameterErrorKind::DimensionMismatch)));
+        }
+        let heights = image.pixels().map(|pixel| pixel[0] as f32 / 255.0).collect();
+        Ok(Self::new(origin, size, columns, rows, heights))
+    }
+
+    fn cell_size(&self) -> (f32, f32) {
+        (
+            self.size.x / (self.columns - 1) as f32,
+            self.size.z / (self.rows - 1) as f32,
+        )
+    }
+
+    fn vertex(&self, i: usize, j: usize) -> Vector3D {
+        let (dx, dz) = self.cell_size();
+        Vector3D::new(
+            self.origin.x + i as f32 * dx,
+            self.origin.y + self.heights[j * self.columns + i] * self.size.y,
+            self.origin.z + j as f32 * dz,
+        )
+    }
+
+    fn vertex_normal(&self, i: usize, j: usize) -> Vector3D {
+        let (dx, dz) = self.cell_size();
+        let height = |i: usize, j: usize| self.heights[j * self.columns + i] * self.size.y;
+        let (i0, i1) = (i.saturating_sub(1), (i + 1).min(self.columns - 1));
+        let (j0, j1) = (j.saturating_sub(1), (j + 1).min(self.rows - 1));
+        let slope_x = (height(i1, j) - height(i0, j)) / ((i1 - i0) as f32 * dx);
+        let slope_z = (height(i, j1) - height(i, j0)) / ((j1 - j0) as f32 * dz);
+        Vector3D::new(-slope_x, 1.0, -slope_z).normalize()
+    }
+
+    fn intersect_cell(&self, ray: &Ray, i: usize, j: usize) -> Option<f32> {
+        let p00 = self.vertex(i, j);
+        let p10 = self.vertex(i + 1, j);
+        let p01 = self.vertex(i, j + 1);
+        let p11 = self.vertex(i + 1, j + 1);
+
+        let first = intersect_triangle(ray, p00, p10, p11);
+        let second = intersect_triangle(ray, p00, p11, p01);
+        match (first, second) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+}
+
+impl Object for Heightfield {
+    // Walks the grid cells crossed by the ray with a 2D DDA, testing the two triangles of each cell
+    fn intersect(&self, ray: &Ray) -> Option<Intersection> {
+        let (t_enter, t_exit) = Aabb::new(self.origin, self.origin + self.size).intersect(ray)?;
+        let t_start = t_enter.max(0.0);
+        if t_start > t_exit {
+            return None;
+        }
+
+        let (dx, dz) = self.cell_size();
+        let start = ray.point_at(t_start);
+        let last_column = self.columns as isize - 2;
+        let last_row = self.rows as isize - 2;
+        let mut i = (((start.x - self.origin.x) / dx).floor() as isize).clamp(0, last_column);
+        let mut j = (((start.z - self.origin.z) / dz).floor() as isize).clamp(0, last_row);
+
+        let axis_setup = |direction: f32, origin: f32, grid_origin: f32, cell: f32, index: isize| {
+            if direction.abs() < 1e-12 {
+                return (0, f32::INFINITY, f32::INFINITY);
+            }
+            let step = if direction > 0.0 { 1 } else { -1 };
+            let boundary = grid_origin + (index + if step > 0 { 1 } else { 0 }) as f32 * cell;
+            (step, (boundary - origin) / direction, cell / direction.abs())
+        };
+        let (step_x, mut t_max_x, t_delta_x) = axis_setup(ray.direction.x, ray.origin.x, self.origin.x, dx, i);
+        let (step_z, mut t_max_z, t_delta_z) = axis_setup(ray.direction.z, ray.origin.z, self.origin.z, dz, j);
+
+        loop {
+            if let Some(t) = self.intersect_cell(ray, i as usize, j as usize) {
+                let point = ray.point_at(t);
+                let u = (point.x - self.origin.x) / self.size.x;
+                let v = (point.z - self.origin.z) / self.size.z;
+                let tangent = Vector3D::new(self.size.x, 0.0, 0.0);
+                let bitangent = Vector3D::new(0.0, 0.0, self.size.z);
+                return Some(
+                    Intersection::new(t, point, self.normal(&point))
+                        .with_uv(u, v)
+                        .with_tangents(tangent, bitangent),
+                );
+            }
+
+            if t_max_x.min(t_max_z) > t_exit {
+                return None;
+            }
+            if t_max_x < t_max_z {
+                i += step_x;
+                t_max_x += t_delta_x;
+            } else {
+                j += step_z;
+                t_max_z += t_delta_z;
+            }
+            if i < 0 || i > last_column || j < 0 || j > last_row {
+                return None;
+            }
+        }
+    }
+
+    // Bilinear blend of the vertex normals around the point
+    fn normal(&self, point: &Vector3D) -> Vector3D {
+        let (dx, dz) = self.cell_size();
+        let fx = ((point.x - self.origin.x) / dx).clamp(0.0, (self.columns - 1) as f32);
+        let fz = ((point.z - self.origin.z) / dz).clamp(0.0, (self.rows - 1) as f32);
+        let i = (fx.floor() as usize).min(self.columns - 2);
+        let j = (fz.floor() as usize).min(self.rows - 2);
+        let (u, v) = (fx - i as f32, fz - j as f32);
+
+        let bottom = self.vertex_normal(i, j) * (1.0 - u) + self.vertex_normal(i + 1, j) * u;
+        let top = self.vertex_normal(i, j + 1) * (1.0 - u) + self.vertex_normal(i + 1, j + 1) * u;
+        (bottom * (1.0 - v) + top * v).normalize()
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        Some(Aabb::new(self.origin, self.origin + self.size))
+    }
+}
+
+// Möller–Trumbore ray/triangle test, returning the hit distance
+fn intersect_triangle(ray: &Ray, a: Vector3D, b: Vector3D, c: Vector3D) -> Option<f32> {
+    let edge1 = b - a;
+    let edge2 = c - a;
+    let p = ray.direction.cross(&edge2);
+    let determinant = edge1.dot(&p);
+    if determinant.abs() < 1e-9 {
+        return None;
+    }
+
+    let inv_determinant = 1.0 / determinant;
+    let s = ray.origin - a;
+    let u = s.dot(&p) * inv_determinant;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+
+    let q = s.cross(&edge1);
+    let v = ray.direction.dot(&q) * inv_determinant;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+
+    let t = edge2.dot(&q) * inv_determinant;
+    if t > 1e-4 {
+        Some(t)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,103 @@
+use crate::math::vector3d::Vector3D;
+use crate::math::aabb::Aabb;
+use crate::core::ray::Ray;
+use super::object::{Object, Intersection};
+
+type ImplicitFn = dyn Fn(Vector3D) -> f32 + Send + Sync;
+
+// The surface f(x, y, z) = 0 inside `bounds`, found by bracketing a sign change of f
+// along the ray and refining the root by bisection.
+pub struct ImplicitSurface {
+    function: Box<ImplicitFn>,
+    pub bounds: Aabb,
+    pub samples: u32,
+    pub tolerance: f32,
+}
+
+impl ImplicitSurface {
+    pub fn new<F>(function: F, bounds: Aabb) -> Self
+    where
+        F: Fn(Vector3D) -> f32 + Send + Sync + 'static,
+    {
+        Self {
+            function: Box::new(function),
+            bounds,
+            samples: 200,
+            tolerance: 1e-5,
+        }
+    }
+
+    pub fn with_samples(mut self, samples: u32) -> Self {
+        self.samples = samples.max(1);
+        self
+    }
+
+    pub fn with_tolerance(mut self, tolerance: f32) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    pub fn value(&self, p: Vector3D) -> f32 {
+        (self.function)(p)
+    }
+
+    fn refine(&self, ray: &Ray, mut t0: f32, mut t1: f32, mut f0: f32) -> f32 {
+        while t1 - t0 > self.tolerance {
+            let mid = 0.5 * (t0 + t1);
+            let f_mid = self.value(ray.point_at(mid));
+            if (f_mid < 0.0) == (f0 < 0.0) {
+                t0 = mid;
+                f0 = f_mid;
+            } else {
+                t1 = mid;
+            }
+        }
+        0.5 * (t0 + t1)
+    }
+}
+
+impl Object for ImplicitSurface {
+    fn intersect(&self, ray: &Ray) -> Option<Intersection> {
+        let (t_enter, t_exit) = self.bounds.intersect(ray)?;
+        let t_start = t_enter.max(1e-4);
+        if t_start >= t_exit {
+            return None;
+        }
+
+        let step = (t_exit - t_start) / self.samples as f32;
+        let mut t_previous = t_start;
+        let mut f_previous = self.value(ray.point_at(t_start));
+
+        for k in 1..=self.samples {
+            let t = t_start + step * k as f32;
+            let f = self.value(ray.point_at(t));
+            if f == 0.0 || (f < 0.0) != (f_previous < 0.0) {
+                let t = self.refine(ray, t_previous, t, f_previous);
+                let point = ray.point_at(t);
+                return Some(Intersection::new(t, point, self.normal(&point)));
+            }
+            t_previous = t;
+            f_previous = f;
+        }
+
+        None
+    }
+
+    // Gradient of f by central differences, pointing towards positive values
+    fn normal(&self, point: &Vector3D) -> Vector3D {
+        let h = 1e-3;
+        let dx = Vector3D::new(h, 0.0, 0.0);
+        let dy = Vector3D::new(0.0, h, 0.0);
+        let dz = Vector3D::new(0.0, 0.0, h);
+        Vector3D::new(
+            self.value(*point + dx) - self.value(*point - dx),
+            self.value(*point + dy) - self.value(*point - dy),
+            self.value(*point + dz) - self.value(*point - dz),
+        )
+        .normalize()
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        Some(self.bounds)
+    }
+}
@@ -0,0 +1,183 @@
+use std::sync::Arc;
+use crate::math::vector3d::Vector3D;
+use crate::math::aabb::Aabb;
+use crate::math::transform::Transform;
+use crate::math::motion::Motion;
+use crate::core::ray::Ray;
+use crate::materials::material::Material;
+use super::object::{Object, Intersection, LightSample, Span};
+
+// One placement of shared geometry. Many instances can point at the same `Arc`,
+// each with its own object-to-world transform and optional material override. A moving
+// instance follows `motion` instead, placed wherever the ray's time finds it.
+pub struct Instance {
+    pub geometry: Arc<dyn Object>,
+    pub transform: Transform,
+    pub material: Option<Arc<Material>>,
+    pub motion: Option<Motion>,
+}
+
+impl Instance {
+    pub fn new(geometry: Arc<dyn Object>, transform: Transform) -> Self {
+        Self { geometry, transform, material: None, motion: None }
+    }
+
+    pub fn with_material(mut self, material: Arc<Material>) -> Self {
+        self.material = Some(material);
+        self
+    }
+
+    pub fn with_motion(mut self, motion: Motion) -> Self {
+        if !motion.is_empty() {
+            self.motion = Some(motion);
+        }
+        self
+    }
+
+    pub fn transform_at(&self, time: f32) -> Transform {
+        match &self.motion {
+            Some(motion) => motion.at(time),
+            None => self.transform,
+        }
+    }
+
+    fn to_world(&self, mut hit: Intersection, ray: &Ray, scale: f32, transform: &Transform) -> Intersection {
+        hit.t *= scale;
+        hit.point = ray.point_at(hit.t);
+        hit.normal = transform.normal(&hit.normal);
+        hit.tangent = transform.vector(&hit.tangent);
+        hit.bitangent = transform.vector(&hit.bitangent);
+        if self.material.is_some() {
+            hit.material = self.material.clone();
+        }
+        hit
+    }
+
+    // Solid-angle density in world space of a point sampled with `local_pdf` in local
+    // space: to area density, through the transform's area scaling, back to solid angle
+    #[allow(clippy::too_many_arguments)]
+    fn world_pdf(
+        &self,
+        transform: &Transform,
+        local_pdf: f32,
+        local_reference: &Vector3D,
+        local_point: &Vector3D,
+        local_normal: &Vector3D,
+        reference: &Vector3D,
+        point: &Vector3D,
+        normal: &Vector3D,
+    ) -> Option<f32> {
+        let solid_to_area = |reference: &Vector3D, point: &Vector3D, normal: &Vector3D| {
+            let offset = *point - *reference;
+            let distance2 = offset.dot(&offset);
+            normal.dot(&offset).abs() / (distance2 * distance2.sqrt())
+        };
+        let local_area_pdf = local_pdf * solid_to_area(local_reference, local_point, local_normal);
+        let area_pdf = local_area_pdf / transform.area_scale(&local_normal.normalize());
+        let to_solid = solid_to_area(reference, point, normal);
+        if to_solid <= 0.0 || !area_pdf.is_finite() {
+            return None;
+        }
+        Some(area_pdf / to_solid)
+    }
+}
+
+impl Object for Instance {
+    fn intersect(&self, ray: &Ray) -> Option<Intersection> {
+        let transform = self.transform_at(ray.time);
+        let (local_ray, scale) = transform.ray_to_local(ray);
+        let hit = self.geometry.intersect(&local_ray)?;
+        Some(self.to_world(hit, ray, scale, &transform))
+    }
+
+    // Moving instances are taken where they are at time 0, as for `sample_light`
+    fn normal(&self, point: &Vector3D) -> Vector3D {
+        self.normal_at(point, 0.0)
+    }
+
+    fn normal_at(&self, point: &Vector3D, time: f32) -> Vector3D {
+        let transform = self.transform_at(time);
+        let local_point = transform.inverse.transform_point(point);
+        transform.normal(&self.geometry.normal_at(&local_point, time))
+    }
+
+    fn spans(&self, ray: &Ray) -> Vec<Span> {
+        let transform = self.transform_at(ray.time);
+        let (local_ray, scale) = transform.ray_to_local(ray);
+        self.geometry
+            .spans(&local_ray)
+            .into_iter()
+            .map(|span| Span {
+                enter: self.to_world(span.enter, ray, scale, &transform),
+                exit: self.to_world(span.exit, ray, scale, &transform),
+            })
+            .collect()
+    }
+
+    // Moving instances are bounded over their whole motion
+    fn bounds(&self) -> Option<Aabb> {
+        let local = self.geometry.bounds()?;
+        match &self.motion {
+            Some(motion) => Some(motion.bounds(&local)),
+            None => Some(self.transform.bounds(&local)),
+        }
+    }
+
+    // Sampled in local space, the density carried over through the area it covers.
+    // Moving instances are sampled where they are at time 0.
+    fn sample_light(&self, reference: &Vector3D, u1: f32, u2: f32) -> Option<LightSample> {
+        self.sample_light_at(reference, 0.0, u1, u2)
+    }
+
+    fn sample_light_at(&self, reference: &Vector3D, time: f32, u1: f32, u2: f32) -> Option<LightSample> {
+        let transform = self.transform_at(time);
+        let local_reference = transform.inverse.transform_point(reference);
+        let local = self.geometry.sample_light(&local_reference, u1, u2)?;
+        let point = transform.point(&local.point);
+        let normal = transform.normal(&local.normal);
+        let pdf = self.world_pdf(&transform, local.pdf, &local_reference, &local.point, &local.normal, reference, &point, &normal)?;
+        Some(LightSample { point, normal, uv: local.uv, pdf })
+    }
+
+    fn light_pdf(&self, ray: &Ray) -> f32 {
+        let transform = self.transform_at(ray.time);
+        let (local_ray, _) = transform.ray_to_local(ray);
+        let local_pdf = self.geometry.light_pdf(&local_ray);
+        if local_pdf <= 0.0 {
+            return 0.0;
+        }
+        let hit = match self.geometry.intersect(&local_ray) {
+            Some(hit) => hit,
+            None => return 0.0,
+        };
+        let point = transform.point(&hit.point);
+        let normal = transform.normal(&hit.normal);
+        self.world_pdf(&transform, local_pdf, &local_ray.origin, &hit.point, &hit.normal, &ray.origin, &point, &normal)
+            .unwrap_or(0.0)
+    }
+
+    fn emission(&self) -> Option<&Material> {
+        self.material.as_deref().filter(|material| material.is_emissive())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::sphere::Sphere;
+
+    #[test]
+    fn moving_instance_normals_follow_the_motion() {
+        let motion = Motion::new()
+            .with_key(0.0, Transform::identity())
+            .with_key(1.0, Transform::translate(Vector3D::new(10.0, 0.0, 0.0)));
+        let sphere = Instance::new(Arc::new(Sphere::new(Vector3D::new(0.0, 0.0, 0.0), 1.0)), Transform::identity()).with_motion(motion);
+
+        // Straight down onto the top of the sphere where it is at the end of the motion
+        let ray = Ray::new(Vector3D::new(10.5, 5.0, 0.0), Vector3D::new(0.0, -1.0, 0.0)).with_time(1.0);
+        let hit = sphere.intersect(&ray).expect("the moved sphere is hit");
+        let normal = sphere.normal_at(&hit.point, 1.0);
+        assert!((normal - hit.normal.normalize()).length() < 1e-4);
+        assert!((normal - Vector3D::new(0.5, 0.75f32.sqrt(), 0.0)).length() < 1e-4);
+    }
+}
@@ -2,4 +2,10 @@ pub mod object;
 pub mod sphere;
 pub mod plane;
 pub mod cylinder;
-pub mod cube;
\ No newline at end of file
+pub mod cube;
+pub mod csg;
+pub mod sdf;
+pub mod heightfield;
+pub mod implicit;
+pub mod instance;
+pub mod rectangle;
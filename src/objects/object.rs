@@ -1,13 +1,101 @@
+use std::sync::Arc;
 use crate::math::vector3d::Vector3D;
+use crate::math::aabb::Aabb;
 use crate::core::ray::Ray;
+use crate::materials::material::Material;
 
+#[derive(Clone)]
 pub struct Intersection {
     pub t: f32,
     pub point: Vector3D,
     pub normal: Vector3D,
+    // Surface texture coordinates, usually in [0, 1]
+    pub uv: (f32, f32),
+    // Tangent frame: derivatives of the hit point with respect to u and v
+    pub tangent: Vector3D,
+    pub bitangent: Vector3D,
+    // None leaves the surface with the renderer's default material
+    pub material: Option<Arc<Material>>,
+}
+
+impl Intersection {
+    pub fn new(t: f32, point: Vector3D, normal: Vector3D) -> Self {
+        let (tangent, bitangent) = normal.orthonormal_basis();
+        Self { t, point, normal, uv: (0.0, 0.0), tangent, bitangent, material: None }
+    }
+
+    pub fn with_uv(mut self, u: f32, v: f32) -> Self {
+        self.uv = (u, v);
+        self
+    }
+
+    pub fn with_tangents(mut self, tangent: Vector3D, bitangent: Vector3D) -> Self {
+        self.tangent = tangent;
+        self.bitangent = bitangent;
+        self
+    }
+}
+
+// A stretch of the ray's line, between an entry and an exit, that lies inside a solid.
+// Unbounded solids use infinite `t` values at their open ends.
+#[derive(Clone)]
+pub struct Span {
+    pub enter: Intersection,
+    pub exit: Intersection,
+}
+
+// A point picked on an emissive surface for direct lighting, with the density of picking
+// it per unit solid angle around the shaded point.
+pub struct LightSample {
+    pub point: Vector3D,
+    pub normal: Vector3D,
+    // Texture coordinates of the point, for lamps with textured emission
+    pub uv: (f32, f32),
+    pub pdf: f32,
 }
 
 pub trait Object: Send + Sync {
     fn intersect(&self, ray: &Ray) -> Option<Intersection>;
     fn normal(&self, point: &Vector3D) -> Vector3D;
-}
\ No newline at end of file
+
+    // The normal at `point` with the object where it is at scene `time`; only moving
+    // objects differ from `normal`
+    fn normal_at(&self, point: &Vector3D, _time: f32) -> Vector3D {
+        self.normal(point)
+    }
+
+    // Every interval where the full line through `ray` (negative `t` included) is inside
+    // the object, sorted by entry. Objects without an interior return no spans and
+    // cannot be used as CSG operands.
+    fn spans(&self, _ray: &Ray) -> Vec<Span> {
+        Vec::new()
+    }
+
+    // World-space bounding box used by the BVH; None for unbounded objects such as planes,
+    // which are tested against every ray.
+    fn bounds(&self) -> Option<Aabb> {
+        None
+    }
+
+    // Point on the surface visible from `reference`, for objects used as lamps. Shapes
+    // that can't be sampled return None and only light the scene when rays hit them.
+    fn sample_light(&self, _reference: &Vector3D, _u1: f32, _u2: f32) -> Option<LightSample> {
+        None
+    }
+
+    // Like `sample_light`, with the object where it is at scene `time`
+    fn sample_light_at(&self, reference: &Vector3D, _time: f32, u1: f32, u2: f32) -> Option<LightSample> {
+        self.sample_light(reference, u1, u2)
+    }
+
+    // Solid-angle density `sample_light` gives the ray's direction, 0 if it misses
+    fn light_pdf(&self, _ray: &Ray) -> f32 {
+        0.0
+    }
+
+    // The glowing material that makes the object a lamp; None for objects that don't
+    // emit light
+    fn emission(&self) -> Option<&Material> {
+        None
+    }
+}
@@ -1,6 +1,6 @@
 use crate::math::vector3d::Vector3D;
 use crate::core::ray::Ray;
-use super::object::{Object, Intersection};
+use super::object::{Object, Intersection, Span};
 
 pub struct Plane {
     pub point: Vector3D,
@@ -11,6 +11,13 @@ impl Plane {
     pub fn new(point: Vector3D, normal: Vector3D) -> Self {
         Self { point, normal: normal.normalize() }
     }
+
+    // World units along the plane, so textures tile once per unit
+    fn uv(&self, point: &Vector3D) -> (f32, f32) {
+        let (tangent, bitangent) = self.normal.orthonormal_basis();
+        let d = *point - self.point;
+        (d.dot(&tangent), d.dot(&bitangent))
+    }
 }
 
 impl Object for Plane {
@@ -21,7 +28,9 @@ impl Object for Plane {
             let t = v.dot(&self.normal) / denom;
             if t >= 0.0 {
                 let point = ray.point_at(t);
-                Some(Intersection { t, point, normal: self.normal })
+                let (u, v) = self.uv(&point);
+                let (tangent, bitangent) = self.normal.orthonormal_basis();
+                Some(Intersection::new(t, point, self.normal).with_uv(u, v).with_tangents(tangent, bitangent))
             } else {
                 None
             }
@@ -33,4 +42,29 @@ impl Object for Plane {
     fn normal(&self, _point: &Vector3D) -> Vector3D {
         self.normal
     }
+
+    // As a solid, the plane is the half-space lying behind its normal.
+    fn spans(&self, ray: &Ray) -> Vec<Span> {
+        let boundary = |t: f32| {
+            let point = ray.point_at(t);
+            let (u, v) = self.uv(&point);
+            let (tangent, bitangent) = self.normal.orthonormal_basis();
+            Intersection::new(t, point, self.normal).with_uv(u, v).with_tangents(tangent, bitangent)
+        };
+        let denom = ray.direction.dot(&self.normal);
+
+        if denom.abs() <= 1e-6 {
+            if (ray.origin - self.point).dot(&self.normal) < 0.0 {
+                return vec![Span { enter: boundary(f32::NEG_INFINITY), exit: boundary(f32::INFINITY) }];
+            }
+            return Vec::new();
+        }
+
+        let t = (self.point - ray.origin).dot(&self.normal) / denom;
+        if denom < 0.0 {
+            vec![Span { enter: boundary(t), exit: boundary(f32::INFINITY) }]
+        } else {
+            vec![Span { enter: boundary(f32::NEG_INFINITY), exit: boundary(t) }]
+        }
+    }
 }
\ No newline at end of file
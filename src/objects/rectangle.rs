@@ -0,0 +1,97 @@
+use crate::math::vector3d::Vector3D;
+use crate::math::aabb::Aabb;
+use crate::core::ray::Ray;
+use super::object::{Object, Intersection, LightSample};
+
+// Flat parallelogram spanned by two edges from a corner, facing along edge_u × edge_v.
+// Mostly used as an area lamp.
+pub struct Rectangle {
+    pub corner: Vector3D,
+    pub edge_u: Vector3D,
+    pub edge_v: Vector3D,
+}
+
+impl Rectangle {
+    pub fn new(corner: Vector3D, edge_u: Vector3D, edge_v: Vector3D) -> Self {
+        Self { corner, edge_u, edge_v }
+    }
+
+    // Centred on `center`, facing `normal`, `width` by `height`
+    pub fn centered(center: Vector3D, normal: Vector3D, width: f32, height: f32) -> Self {
+        let (tangent, bitangent) = normal.normalize().orthonormal_basis();
+        let (edge_u, edge_v) = (tangent * width, bitangent * height);
+        Self::new(center - (edge_u + edge_v) * 0.5, edge_u, edge_v)
+    }
+
+    pub fn area(&self) -> f32 {
+        self.edge_u.cross(&self.edge_v).length()
+    }
+
+    fn unit_normal(&self) -> Vector3D {
+        self.edge_u.cross(&self.edge_v).normalize()
+    }
+}
+
+impl Object for Rectangle {
+    fn intersect(&self, ray: &Ray) -> Option<Intersection> {
+        let normal = self.unit_normal();
+        let denom = ray.direction.dot(&normal);
+        if denom.abs() <= 1e-8 {
+            return None;
+        }
+        let t = (self.corner - ray.origin).dot(&normal) / denom;
+        if t <= 0.0 {
+            return None;
+        }
+
+        // Coordinates along the edges through the reciprocal basis
+        let point = ray.point_at(t);
+        let offset = point - self.corner;
+        let w = self.edge_u.cross(&self.edge_v);
+        let w = w * (1.0 / w.dot(&w));
+        let u = offset.cross(&self.edge_v).dot(&w);
+        let v = self.edge_u.cross(&offset).dot(&w);
+        if !(0.0..=1.0).contains(&u) || !(0.0..=1.0).contains(&v) {
+            return None;
+        }
+        Some(Intersection::new(t, point, normal).with_uv(u, v).with_tangents(self.edge_u, self.edge_v))
+    }
+
+    fn normal(&self, _point: &Vector3D) -> Vector3D {
+        self.unit_normal()
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        let bounds = Aabb::empty()
+            .include(&self.corner)
+            .include(&(self.corner + self.edge_u))
+            .include(&(self.corner + self.edge_v))
+            .include(&(self.corner + self.edge_u + self.edge_v));
+        // Give the flat box some thickness so slab tests along its normal still hit
+        let pad = Vector3D::new(1e-4, 1e-4, 1e-4);
+        Some(Aabb::new(bounds.min - pad, bounds.max + pad))
+    }
+
+    // Uniform over the area, converted to solid angle
+    fn sample_light(&self, reference: &Vector3D, u1: f32, u2: f32) -> Option<LightSample> {
+        let point = self.corner + self.edge_u * u1 + self.edge_v * u2;
+        let normal = self.unit_normal();
+        let to_point = point - *reference;
+        let distance2 = to_point.dot(&to_point);
+        let cos = normal.dot(&to_point).abs() / distance2.sqrt();
+        if cos <= 1e-6 {
+            return None;
+        }
+        Some(LightSample { point, normal, uv: (u1, u2), pdf: distance2 / (cos * self.area()) })
+    }
+
+    fn light_pdf(&self, ray: &Ray) -> f32 {
+        match self.intersect(ray) {
+            Some(hit) => {
+                let cos = hit.normal.dot(&ray.direction).abs();
+                if cos <= 1e-6 { 0.0 } else { hit.t * hit.t / (cos * self.area()) }
+            }
+            None => 0.0,
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use crate::math::vector3d::Vector3D;
+use crate::math::aabb::Aabb;
+use crate::core::ray::Ray;
+use super::object::{Object, Intersection};
+
+// A small composable signed distance tree. Distances are negative inside the shape.
+pub enum Sdf {
+    Sphere { center: Vector3D, radius: f32 },
+    Box { center: Vector3D, half_extents: Vector3D },
+    Torus { center: Vector3D, major_radius: f32, minor_radius: f32 },
+    Capsule { a: Vector3D, b: Vector3D, radius: f32 },
+    Plane { normal: Vector3D, offset: f32 },
+    Union(Box<Sdf>, Box<Sdf>),
+    Intersection(Box<Sdf>, Box<Sdf>),
+    Subtract(Box<Sdf>, Box<Sdf>),
+    SmoothUnion(Box<Sdf>, Box<Sdf>, f32),
+    SmoothSubtract(Box<Sdf>, Box<Sdf>, f32),
+    Round(Box<Sdf>, f32),
+    Translate(Box<Sdf>, Vector3D),
+    // Rotates the xz plane by the given angle (radians) per unit of height
+    Twist(Box<Sdf>, f32),
+    // Infinite repetition with the given period per axis, 0 leaves an axis alone
+    Repeat(Box<Sdf>, Vector3D),
+}
+
+impl Sdf {
+    pub fn distance(&self, p: Vector3D) -> f32 {
+        match self {
+            Sdf::Sphere { center, radius } => (p - *center).length() - radius,
+            Sdf::Box { center, half_extents } => {
+                let d = p - *center;
+                let q = Vector3D::new(
+                    d.x.abs() - half_extents.x,
+                    d.y.abs() - half_extents.y,
+                    d.z.abs() - half_extents.z,
+                );
+                let outside = Vector3D::new(q.x.max(0.0), q.y.max(0.0), q.z.max(0.0)).length();
+                outside + q.x.max(q.y).max(q.z).min(0.0)
+            }
+            Sdf::Torus { center, major_radius, minor_radius } => {
+                let d = p - *center;
+                let ring = (d.x * d.x + d.z * d.z).sqrt() - major_radius;
+                (ring * ring + d.y * d.y).sqrt() - minor_radius
+            }
+            Sdf::Capsule { a, b, radius } => {
+                let pa = p - *a;
+                let ba = *b - *a;
+                let h = (pa.dot(&ba) / ba.dot(&ba)).clamp(0.0, 1.0);
+                (pa - ba * h).length() - radius
+            }
+            Sdf::Plane { normal, offset } => p.dot(normal) - offset,
+            Sdf::Union(a, b) => a.distance(p).min(b.distance(p)),
+            Sdf::Intersection(a, b) => a.distance(p).max(b.distance(p)),
+            Sdf::Subtract(a, b) => a.distance(p).max(-b.distance(p)),
+            Sdf::SmoothUnion(a, b, k) => {
+                let (d1, d2) = (a.distance(p), b.distance(p));
+                let h = (0.5 + 0.5 * (d2 - d1) / k).clamp(0.0, 1.0);
+                mix(d2, d1, h) - k * h * (1.0 - h)
+            }
+            Sdf::SmoothSubtract(a, b, k) => {
+                let (d1, d2) = (a.distance(p), b.distance(p));
+                let h = (0.5 - 0.5 * (d1 + d2) / k).clamp(0.0, 1.0);
+                mix(d1, -d2, h) + k * h * (1.0 - h)
+            }
+            Sdf::Round(a, radius) => a.distance(p) - radius,
+            Sdf::Translate(a, offset) => a.distance(p - *offset),
+            Sdf::Twist(a, rate) => {
+                let (sin, cos) = (rate * p.y).sin_cos();
+                a.distance(Vector3D::new(cos * p.x - sin * p.z, p.y, sin * p.x + cos * p.z))
+            }
+            Sdf::Repeat(a, period) => {
+                let wrap = |v: f32, c: f32| if c > 0.0 { v - c * (v / c).round() } else { v };
+                a.distance(Vector3D::new(wrap(p.x, period.x), wrap(p.y, period.y), wrap(p.z, period.z)))
+            }
+        }
+    }
+
+    pub fn union(self, other: Sdf) -> Sdf {
+        Sdf::Union(Box::new(self), Box::new(other))
+    }
+
+    pub fn intersection(self, other: Sdf) -> Sdf {
+        Sdf::Intersection(Box::new(self), Box::new(other))
+    }
+
+    pub fn subtract(self, other: Sdf) -> Sdf {
+        Sdf::Subtract(Box::new(self), Box::new(other))
+    }
+
+    pub fn smooth_union(self, other: Sdf, k: f32) -> Sdf {
+        Sdf::SmoothUnion(Box::new(self), Box::new(other), k)
+    }
+
+    pub fn smooth_subtract(self, other: Sdf, k: f32) -> Sdf {
+        Sdf::SmoothSubtract(Box::new(self), Box::new(other), k)
+    }
+
+    pub fn round(self, radius: f32) -> Sdf {
+        Sdf::Round(Box::new(self), radius)
+    }
+
+    pub fn translate(self, offset: Vector3D) -> Sdf {
+        Sdf::Translate(Box::new(self), offset)
+    }
+
+    pub fn twist(self, rate: f32) -> Sdf {
+        Sdf::Twist(Box::new(self), rate)
+    }
+
+    pub fn repeat(self, period: Vector3D) -> Sdf {
+        Sdf::Repeat(Box::new(self), period)
+    }
+}
+
+fn mix(a: f32, b: f32, h: f32) -> f32 {
+    a + (b - a) * h
+}
+
+type DistanceFn = dyn Fn(Vector3D) -> f32 + Send + Sync;
+
+pub struct SdfObject {
+    distance: Box<DistanceFn>,
+    pub epsilon: f32,
+    pub max_steps: u32,
+    pub max_distance: f32,
+    // Fraction of the distance bound advanced per step; lower it for fields that
+    // overestimate distance (twists, fractals).
+    pub step_scale: f32,
+    // Optional box enclosing the surface, letting the BVH skip the marcher entirely
+    pub bounds: Option<Aabb>,
+}
+
+impl SdfObject {
+    pub fn new<F>(distance: F) -> Self
+    where
+        F: Fn(Vector3D) -> f32 + Send + Sync + 'static,
+    {
+        Self {
+            distance: Box::new(distance),
+            epsilon: 1e-4,
+            max_steps: 256,
+            max_distance: 1000.0,
+            step_scale: 1.0,
+            bounds: None,
+        }
+    }
+
+    pub fn from_tree(sdf: Sdf) -> Self {
+        Self::new(move |p| sdf.distance(p))
+    }
+
+    pub fn with_epsilon(mut self, epsilon: f32) -> Self {
+        self.epsilon = epsilon;
+        self
+    }
+
+    pub fn with_max_steps(mut self, max_steps: u32) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    pub fn with_max_distance(mut self, max_distance: f32) -> Self {
+        self.max_distance = max_distance;
+        self
+    }
+
+    pub fn with_step_scale(mut self, step_scale: f32) -> Self {
+        self.step_scale = step_scale;
+        self
+    }
+
+    pub fn with_bounds(mut self, bounds: Aabb) -> Self {
+        self.bounds = Some(bounds);
+        self
+    }
+
+    pub fn distance(&self, p: Vector3D) -> f32 {
+        (self.distance)(p)
+    }
+}
+
+impl Object for SdfObject {
+    fn intersect(&self, ray: &Ray) -> Option<Intersection> {
+        let mut t = 0.0;
+
+        for _ in 0..self.max_steps {
+            let point = ray.point_at(t);
+            let d = self.distance(point).abs();
+            if d < self.epsilon * t.max(1.0) {
+                // Step off the surface the ray starts on
+                if t < self.epsilon * 10.0 {
+                    t += self.epsilon * 10.0;
+                    continue;
+                }
+                return Some(Intersection::new(t, point, self.normal(&point)));
+            }
+            t += d * self.step_scale;
+            if t > self.max_distance {
+                break;
+            }
+        }
+
+        None
+    }
+
+    // Central differences of the distance field
+    fn normal(&self, point: &Vector3D) -> Vector3D {
+        let h = self.epsilon.max(1e-4);
+        let dx = Vector3D::new(h, 0.0, 0.0);
+        let dy = Vector3D::new(0.0, h, 0.0);
+        let dz = Vector3D::new(0.0, 0.0, h);
+        Vector3D::new(
+            self.distance(*point + dx) - self.distance(*point - dx),
+            self.distance(*point + dy) - self.distance(*point - dy),
+            self.distance(*point + dz) - self.distance(*point - dz),
+        )
+        .normalize()
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        self.bounds
+    }
+}
@@ -1,6 +1,7 @@
 use crate::math::vector3d::Vector3D;
+use crate::math::aabb::Aabb;
 use crate::core::ray::Ray;
-use super::object::{Object, Intersection};
+use super::object::{Object, Intersection, LightSample, Span};
 
 pub struct Sphere {
     pub center: Vector3D,
@@ -11,6 +12,30 @@ impl Sphere {
     pub fn new(center: Vector3D, radius: f32) -> Self {
         Self { center, radius }
     }
+
+    // Longitude and latitude of the surface direction
+    fn uv(&self, direction: &Vector3D) -> (f32, f32) {
+        let u = 0.5 + direction.z.atan2(direction.x) / (2.0 * std::f32::consts::PI);
+        let v = 0.5 + direction.y.clamp(-1.0, 1.0).asin() / std::f32::consts::PI;
+        (u, v)
+    }
+
+    fn tangents(&self, direction: &Vector3D) -> (Vector3D, Vector3D) {
+        use std::f32::consts::PI;
+        let horizontal = (direction.x * direction.x + direction.z * direction.z).sqrt();
+        if horizontal < 1e-6 {
+            // Poles: any frame around the normal will do
+            let (tangent, bitangent) = direction.orthonormal_basis();
+            return (tangent * (2.0 * PI * self.radius), bitangent * (PI * self.radius));
+        }
+        let tangent = Vector3D::new(-direction.z, 0.0, direction.x) * (2.0 * PI * self.radius);
+        let bitangent = Vector3D::new(
+            -direction.y * direction.x / horizontal,
+            horizontal,
+            -direction.y * direction.z / horizontal,
+        ) * (PI * self.radius);
+        (tangent, bitangent)
+    }
 }
 
 impl Object for Sphere {
@@ -28,7 +53,9 @@ impl Object for Sphere {
             if t > 0.0 {
                 let point = ray.point_at(t);
                 let normal = (point - self.center).normalize();
-                Some(Intersection { t, point, normal })
+                let (u, v) = self.uv(&normal);
+                let (tangent, bitangent) = self.tangents(&normal);
+                Some(Intersection::new(t, point, normal).with_uv(u, v).with_tangents(tangent, bitangent))
             } else {
                 None
             }
@@ -38,4 +65,72 @@ impl Object for Sphere {
     fn normal(&self, point: &Vector3D) -> Vector3D {
         (*point - self.center).normalize()
     }
-}
\ No newline at end of file
+
+    fn spans(&self, ray: &Ray) -> Vec<Span> {
+        let oc = ray.origin - self.center;
+        let a = ray.direction.dot(&ray.direction);
+        let b = 2.0 * oc.dot(&ray.direction);
+        let c = oc.dot(&oc) - self.radius * self.radius;
+        let discriminant = b * b - 4.0 * a * c;
+
+        if discriminant < 0.0 {
+            return Vec::new();
+        }
+
+        let sqrt_discriminant = discriminant.sqrt();
+        let hit = |t: f32| {
+            let point = ray.point_at(t);
+            let normal = self.normal(&point);
+            let (u, v) = self.uv(&normal);
+            let (tangent, bitangent) = self.tangents(&normal);
+            Intersection::new(t, point, normal).with_uv(u, v).with_tangents(tangent, bitangent)
+        };
+
+        vec![Span {
+            enter: hit((-b - sqrt_discriminant) / (2.0 * a)),
+            exit: hit((-b + sqrt_discriminant) / (2.0 * a)),
+        }]
+    }
+
+    // Uniform over the cone of directions the sphere subtends. Points inside the sphere
+    // can't sample it.
+    fn sample_light(&self, reference: &Vector3D, u1: f32, u2: f32) -> Option<LightSample> {
+        let to_center = self.center - *reference;
+        let distance = to_center.length();
+        if distance <= self.radius {
+            return None;
+        }
+        let axis = to_center * (1.0 / distance);
+        let sin2_max = (self.radius / distance).powi(2);
+        let cos_max = (1.0 - sin2_max).max(0.0).sqrt();
+
+        let cos_theta = 1.0 - u1 * (1.0 - cos_max);
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = 2.0 * std::f32::consts::PI * u2;
+        let (tangent, bitangent) = axis.orthonormal_basis();
+        let direction = tangent * (sin_theta * phi.cos()) + bitangent * (sin_theta * phi.sin()) + axis * cos_theta;
+
+        // Distance to the near side; clamped at the silhouette where rounding can miss
+        let along = distance * cos_theta;
+        let across2 = distance * distance * sin_theta * sin_theta;
+        let t = along - (self.radius * self.radius - across2).max(0.0).sqrt();
+        let point = *reference + direction * t;
+        let pdf = 1.0 / (2.0 * std::f32::consts::PI * (1.0 - cos_max).max(1e-7));
+        let normal = self.normal(&point);
+        Some(LightSample { point, normal, uv: self.uv(&normal), pdf })
+    }
+
+    fn light_pdf(&self, ray: &Ray) -> f32 {
+        let distance = (self.center - ray.origin).length();
+        if distance <= self.radius || self.intersect(ray).is_none() {
+            return 0.0;
+        }
+        let cos_max = (1.0 - (self.radius / distance).powi(2)).max(0.0).sqrt();
+        1.0 / (2.0 * std::f32::consts::PI * (1.0 - cos_max).max(1e-7))
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        let extent = Vector3D::new(self.radius, self.radius, self.radius);
+        Some(Aabb::new(self.center - extent, self.center + extent))
+    }
+}
@@ -0,0 +1,44 @@
+use crate::core::color::Color;
+use crate::core::rng::Rng;
+use super::crop::Region;
+
+// Running sums of the samples taken so far, with each pixel's random stream where it
+// left off, so more passes can be added to a render later with the same result as
+// taking all the samples at once.
+#[derive(Debug, Clone)]
+pub struct Accumulation {
+    pub width: u32,
+    pub height: u32,
+    pub sums: Vec<Color>,
+    // Samples taken in every pixel
+    pub samples: u32,
+    pub rngs: Vec<Rng>,
+}
+
+impl Accumulation {
+    pub fn new(width: u32, height: u32, seed: u64) -> Self {
+        Self::for_region(&Region::full(width, height), width, seed)
+    }
+
+    // Just the pixels of `region` in a frame `frame_width` wide. Each pixel keeps the
+    // random stream it has in the full frame, so it comes out the same either way.
+    pub fn for_region(region: &Region, frame_width: u32, seed: u64) -> Self {
+        let rngs = (region.y..region.y + region.height)
+            .flat_map(|y| (region.x..region.x + region.width).map(move |x| y as u64 * frame_width as u64 + x as u64))
+            .map(|pixel| Rng::new(seed, pixel))
+            .collect();
+        Self {
+            width: region.width,
+            height: region.height,
+            sums: vec![Color::new(0.0, 0.0, 0.0); region.pixel_count()],
+            samples: 0,
+            rngs,
+        }
+    }
+
+    // Current estimate, the average of the samples so far
+    pub fn image(&self) -> Vec<Color> {
+        let scale = 1.0 / self.samples.max(1) as f32;
+        self.sums.iter().map(|sum| *sum * scale).collect()
+    }
+}
@@ -0,0 +1,200 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use crate::core::color::Color;
+use crate::core::rng::Rng;
+use super::accumulation::Accumulation;
+use super::crop::Region;
+use super::renderer::Renderer;
+
+// Binary snapshot of an Accumulation: a header of magic, version, the key below and
+// samples, then per pixel the colour sums as raw f32 bits and the random stream's state
+// and increment, all little-endian. Floats are stored bit for bit so a resumed render
+// ends up identical to one that was never stopped.
+const MAGIC: &[u8; 4] = b"RTCP";
+const VERSION: u32 = 2;
+const HEADER_BYTES: u64 = 4 + 4 * 8 + 8 * 2;
+const PIXEL_BYTES: u64 = 4 * 3 + 8 * 2;
+
+// What a checkpoint was rendered from. Every pixel's random stream depends on the seed
+// and its place in the frame, so resuming with any of these changed would blend two
+// different renders.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CheckpointKey {
+    pub frame_width: u32,
+    pub frame_height: u32,
+    pub region: Region,
+    pub seed: u64,
+    // FNV-1a hash of the scene file's text
+    pub scene_hash: u64,
+}
+
+impl CheckpointKey {
+    pub fn new(renderer: &Renderer, scene: &str) -> Self {
+        let scene_hash = scene.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3));
+        Self { frame_width: renderer.width, frame_height: renderer.height, region: renderer.region(), seed: renderer.seed, scene_hash }
+    }
+}
+
+// Writes next to `path` and renames into place, so a crash mid-write leaves the previous
+// checkpoint intact
+pub fn save_checkpoint(path: &Path, key: &CheckpointKey, accumulation: &Accumulation) -> io::Result<()> {
+    let region = key.region;
+    if (accumulation.width, accumulation.height) != (region.width, region.height) {
+        return Err(invalid("accumulation does not match the checkpoint's region"));
+    }
+    let partial = path.with_extension("checkpoint.part");
+    let mut out = BufWriter::new(File::create(&partial)?);
+    out.write_all(MAGIC)?;
+    for value in [VERSION, key.frame_width, key.frame_height, region.x, region.y, region.width, region.height, accumulation.samples] {
+        out.write_all(&value.to_le_bytes())?;
+    }
+    for value in [key.seed, key.scene_hash] {
+        out.write_all(&value.to_le_bytes())?;
+    }
+    for (sum, rng) in accumulation.sums.iter().zip(&accumulation.rngs) {
+        for channel in [sum.r, sum.g, sum.b] {
+            out.write_all(&channel.to_bits().to_le_bytes())?;
+        }
+        out.write_all(&rng.state.to_le_bytes())?;
+        out.write_all(&rng.increment.to_le_bytes())?;
+    }
+    out.into_inner().map_err(|error| error.into_error())?.sync_all()?;
+    fs::rename(&partial, path)
+}
+
+// Fails unless the checkpoint was saved under `key`
+pub fn load_checkpoint(path: &Path, key: &CheckpointKey) -> io::Result<Accumulation> {
+    let file = File::open(path)?;
+    let length = file.metadata()?.len();
+    let mut input = BufReader::new(file);
+    let mut magic = [0u8; 4];
+    input.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid("not a render checkpoint"));
+    }
+    if read_u32(&mut input)? != VERSION {
+        return Err(invalid("unsupported checkpoint version"));
+    }
+    let (frame_width, frame_height) = (read_u32(&mut input)?, read_u32(&mut input)?);
+    let region = Region::new(read_u32(&mut input)?, read_u32(&mut input)?, read_u32(&mut input)?, read_u32(&mut input)?);
+    let samples = read_u32(&mut input)?;
+    let (seed, scene_hash) = (read_u64(&mut input)?, read_u64(&mut input)?);
+
+    if (frame_width, frame_height) != (key.frame_width, key.frame_height) {
+        return Err(invalid(&format!("checkpoint is of a {}x{} frame, the render is {}x{}", frame_width, frame_height, key.frame_width, key.frame_height)));
+    }
+    if region != key.region {
+        let describe = |region: Region| format!("{}x{} pixels at ({}, {})", region.width, region.height, region.x, region.y);
+        return Err(invalid(&format!("checkpoint covers {}, the render {}", describe(region), describe(key.region))));
+    }
+    if seed != key.seed {
+        return Err(invalid(&format!("checkpoint has seed {}, the render {}", seed, key.seed)));
+    }
+    if scene_hash != key.scene_hash {
+        return Err(invalid("checkpoint is of a different scene file"));
+    }
+    // Checked before allocating, so a damaged header can't ask for any amount of memory
+    if length != HEADER_BYTES + region.pixel_count() as u64 * PIXEL_BYTES {
+        return Err(invalid("checkpoint is truncated or the wrong size for its region"));
+    }
+
+    let count = region.pixel_count();
+    let mut sums = Vec::with_capacity(count);
+    let mut rngs = Vec::with_capacity(count);
+    for _ in 0..count {
+        let r = f32::from_bits(read_u32(&mut input)?);
+        let g = f32::from_bits(read_u32(&mut input)?);
+        let b = f32::from_bits(read_u32(&mut input)?);
+        sums.push(Color::new(r, g, b));
+        rngs.push(Rng { state: read_u64(&mut input)?, increment: read_u64(&mut input)? });
+    }
+    Ok(Accumulation { width: region.width, height: region.height, sums, samples, rngs })
+}
+
+fn read_u32(input: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    input.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(input: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0u8; 8];
+    input.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use super::*;
+    use crate::renderer::crop::CropWindow;
+    use crate::scene::scene_file::SceneFile;
+
+    const SCENE: &str = "image width 24 height 16 samples 6
+integrator path depth 3
+camera position 0 1 -6 look_at 0 0 0 up 0 1 0 fov 60
+light position -4 6 -4 color 1 1 1 intensity 1
+sphere center 0 0 0 radius 1
+plane point 0 -1 0 normal 0 1 0
+";
+
+    fn scratch_file(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("rt-{}-{}.checkpoint", name, std::process::id()))
+    }
+
+    fn bits(accumulation: &Accumulation) -> Vec<[u32; 3]> {
+        accumulation.sums.iter().map(|sum| [sum.r.to_bits(), sum.g.to_bits(), sum.b.to_bits()]).collect()
+    }
+
+    #[test]
+    fn resumed_render_matches_an_uninterrupted_one() {
+        let file = SceneFile::parse(SCENE).unwrap();
+        let renderer = file.renderer.with_seed(7).with_crop(CropWindow::Pixels { x: 5, y: 3, width: 12, height: 9 });
+        let mut straight = renderer.accumulation();
+        renderer.render_passes(&file.scene, &file.camera, &mut straight, 6, |_| true);
+
+        // Stopped after 2 of the 6 samples, saved, loaded and finished
+        let key = CheckpointKey::new(&renderer, SCENE);
+        let path = scratch_file("resume");
+        let mut first = renderer.accumulation();
+        renderer.render_passes(&file.scene, &file.camera, &mut first, 2, |_| false);
+        save_checkpoint(&path, &key, &first).unwrap();
+        let mut resumed = load_checkpoint(&path, &key).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(resumed.samples, 2);
+        renderer.render_passes(&file.scene, &file.camera, &mut resumed, 6, |_| true);
+
+        assert_eq!(resumed.samples, straight.samples);
+        assert_eq!(bits(&resumed), bits(&straight));
+    }
+
+    #[test]
+    fn checkpoint_of_another_render_is_rejected() {
+        let file = SceneFile::parse(SCENE).unwrap();
+        let renderer = file.renderer.with_crop(CropWindow::Pixels { x: 0, y: 0, width: 8, height: 8 });
+        let key = CheckpointKey::new(&renderer, SCENE);
+        let path = scratch_file("reject");
+        save_checkpoint(&path, &key, &renderer.accumulation()).unwrap();
+
+        let moved = CheckpointKey { region: Region::new(8, 8, 8, 8), ..key };
+        let reseeded = CheckpointKey { seed: 1, ..key };
+        let edited = CheckpointKey::new(&renderer, &SCENE.replace("radius 1", "radius 2"));
+        for other in [moved, reseeded, edited] {
+            assert!(load_checkpoint(&path, &other).is_err());
+        }
+        assert!(load_checkpoint(&path, &key).is_ok());
+
+        // A header promising more pixels than the file holds
+        let mut bytes = fs::read(&path).unwrap();
+        bytes.truncate(bytes.len() - 1);
+        fs::write(&path, &bytes).unwrap();
+        let error = load_checkpoint(&path, &key).err().unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,85 @@
+use crate::core::color::Color;
+
+// Part of the frame to render. Both forms are measured from the top-left corner of the
+// image, the way it is viewed.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CropWindow {
+    Pixels { x: u32, y: u32, width: u32, height: u32 },
+    // Fractions of the frame in [0, 1]; partly covered pixels are included
+    Normalized { left: f32, top: f32, right: f32, bottom: f32 },
+}
+
+impl CropWindow {
+    // The window covering exactly `region` of a frame `height` pixels tall
+    pub fn from_region(region: &Region, height: u32) -> Self {
+        CropWindow::Pixels { x: region.x, y: height - region.y - region.height, width: region.width, height: region.height }
+    }
+
+    // The pixels covered in a `width` x `height` frame, clipped to it
+    pub fn region(&self, width: u32, height: u32) -> Region {
+        let (x0, top, x1, bottom) = match *self {
+            CropWindow::Pixels { x, y, width: w, height: h } => (x, y, x.saturating_add(w), y.saturating_add(h)),
+            CropWindow::Normalized { left, top, right, bottom } => (
+                (left.clamp(0.0, 1.0) * width as f32).floor() as u32,
+                (top.clamp(0.0, 1.0) * height as f32).floor() as u32,
+                (right.clamp(0.0, 1.0) * width as f32).ceil() as u32,
+                (bottom.clamp(0.0, 1.0) * height as f32).ceil() as u32,
+            ),
+        };
+        let (x0, x1) = (x0.min(width), x1.min(width));
+        let (top, bottom) = (top.min(height), bottom.min(height));
+        Region {
+            x: x0,
+            y: height - bottom.max(top),
+            width: x1.saturating_sub(x0),
+            height: bottom.saturating_sub(top),
+        }
+    }
+}
+
+// Rectangle of pixels in the renderer's image layout, rows counted from the bottom
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Region {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Region {
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Self { x, y, width, height }
+    }
+
+    pub fn full(width: u32, height: u32) -> Self {
+        Self::new(0, 0, width, height)
+    }
+
+    pub fn pixel_count(&self) -> usize {
+        self.width as usize * self.height as usize
+    }
+
+    // Splits the region into tiles of at most `size` x `size` pixels, row by row
+    pub fn tiles(&self, size: u32) -> Vec<Region> {
+        let size = size.max(1);
+        let mut tiles = Vec::new();
+        for y in (self.y..self.y + self.height).step_by(size as usize) {
+            for x in (self.x..self.x + self.width).step_by(size as usize) {
+                let width = size.min(self.x + self.width - x);
+                let height = size.min(self.y + self.height - y);
+                tiles.push(Region::new(x, y, width, height));
+            }
+        }
+        tiles
+    }
+
+    // Copies `image`, rendered for this region, over the same pixels of `target`, a full
+    // frame `frame_width` pixels wide
+    pub fn composite(&self, image: &[Color], target: &mut [Color], frame_width: u32) {
+        for row in 0..self.height {
+            let from = (row * self.width) as usize;
+            let to = ((self.y + row) * frame_width + self.x) as usize;
+            target[to..to + self.width as usize].copy_from_slice(&image[from..from + self.width as usize]);
+        }
+    }
+}
@@ -1 +1,8 @@
-pub mod renderer;
\ No newline at end of file
+#[allow(clippy::module_inception)]
+pub mod renderer;
+pub mod path_tracer;
+pub mod output;
+pub mod accumulation;
+pub mod progressive;
+pub mod checkpoint;
+pub mod crop;
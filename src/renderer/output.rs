@@ -0,0 +1,55 @@
+use std::fs;
+use std::path::Path;
+use image::error::{ParameterError, ParameterErrorKind};
+use image::{ColorType, ImageError, ImageFormat, ImageResult, RgbImage};
+use crate::core::color::Color;
+use super::crop::Region;
+
+const GAMMA: f32 = 2.2;
+
+// 8-bit RGB rows top to bottom, gamma encoded, from a rendered image stored bottom row first
+pub fn to_rgb8(image: &[Color], width: u32, height: u32) -> Vec<u8> {
+    let encode = |value: f32| (value.clamp(0.0, 1.0).powf(1.0 / GAMMA) * 255.0 + 0.5) as u8;
+    let mut bytes = Vec::with_capacity((width * height * 3) as usize);
+    for y in (0..height).rev() {
+        for color in &image[(y * width) as usize..((y + 1) * width) as usize] {
+            bytes.extend_from_slice(&[encode(color.r), encode(color.g), encode(color.b)]);
+        }
+    }
+    bytes
+}
+
+// Written next to `path` first and renamed into place, so an existing file is always a
+// complete image
+pub fn save_png(path: &Path, image: &[Color], width: u32, height: u32) -> ImageResult<()> {
+    let partial = path.with_extension("png.part");
+    image::save_buffer_with_format(&partial, &to_rgb8(image, width, height), width, height, ColorType::Rgb8, ImageFormat::Png)?;
+    fs::rename(&partial, path)?;
+    Ok(())
+}
+
+// Pastes `image`, rendered for `region`, into the PNG at `path`, a full frame of
+// `width` x `height`. A missing file starts out black.
+pub fn composite_png(path: &Path, image: &[Color], region: &Region, width: u32, height: u32) -> ImageResult<()> {
+    let mut frame = if path.exists() {
+        image::open(path)?.into_rgb8()
+    } else {
+        RgbImage::new(width, height)
+    };
+    if frame.dimensions() != (width, height) {
+        return Err(ImageError::Parameter(ParameterError::from_kind(ParameterErrorKind::DimensionMismatch)));
+    }
+    let rows = to_rgb8(image, region.width, region.height);
+    let top = height - region.y - region.height;
+    let row_bytes = (region.width * 3) as usize;
+    let bytes: &mut [u8] = &mut frame;
+    for row in 0..region.height {
+        let to = (((top + row) * width + region.x) * 3) as usize;
+        let from = row as usize * row_bytes;
+        bytes[to..to + row_bytes].copy_from_slice(&rows[from..from + row_bytes]);
+    }
+    let partial = path.with_extension("png.part");
+    frame.save_with_format(&partial, ImageFormat::Png)?;
+    fs::rename(&partial, path)?;
+    Ok(())
+}
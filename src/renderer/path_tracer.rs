@@ -0,0 +1,213 @@
+use std::f32::consts::PI;
+use crate::math::vector3d::Vector3D;
+use crate::core::color::Color;
+use crate::core::ray::Ray;
+use crate::core::rng::Rng;
+use crate::scene::scene::Scene;
+use crate::scene::bvh::Bvh;
+use crate::scene::emitters::LightList;
+use crate::materials::material::Material;
+use crate::materials::bsdf::Frame;
+use crate::media::medium::transmittance;
+use super::renderer::{offset_origin, texture_footprint};
+
+// Stops shadow rays towards a lamp just short of its surface
+const SHADOW_EPSILON: f32 = 1e-3;
+
+// Bounces after which paths may be terminated by Russian roulette
+const ROULETTE_DEPTH: u32 = 3;
+
+// Unidirectional path tracer: point lights and one emissive lamp are sampled at every
+// bounce and the BSDF is importance sampled to continue the path. Lamps found either way
+// are combined with multiple importance sampling (power heuristic). Paths scatter inside
+// participating media by delta tracking.
+pub struct PathTracer {
+    pub max_depth: u32,
+}
+
+impl PathTracer {
+    pub fn new() -> Self {
+        Self { max_depth: 8 }
+    }
+
+    pub fn with_max_depth(mut self, max_depth: u32) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub fn radiance(&self, ray: Ray, scene: &Scene, bvh: &Bvh, lights: &LightList, rng: &mut Rng) -> Color {
+        let default_material = Material::default();
+        let mut radiance = Color::new(0.0, 0.0, 0.0);
+        let mut throughput = Color::new(1.0, 1.0, 1.0);
+        let mut ray = ray;
+        // Density of the BSDF or phase sample that produced `ray`, None for camera rays
+        let mut bsdf_pdf: Option<f32> = None;
+        let mut first_distance = f32::INFINITY;
+
+        for depth in 0..self.max_depth {
+            let hit = bvh.intersect_object(&ray);
+            let distance = hit.as_ref().map_or(f32::INFINITY, |(hit, _)| hit.t);
+            if depth == 0 {
+                first_distance = distance;
+            }
+
+            // Scattering in a medium before reaching the surface; each medium tracks its
+            // own collisions and the nearest one wins
+            let collision = scene.media
+                .iter()
+                .filter_map(|medium| medium.sample_collision(&ray, distance, rng).map(|t| (medium, t)))
+                .min_by(|a, b| a.1.total_cmp(&b.1));
+            if let Some((medium, t)) = collision {
+                if rng.next_f32() >= medium.albedo() {
+                    break;
+                }
+                throughput = throughput * medium.color;
+                let point = ray.point_at(t);
+                let incoming = ray.direction;
+                let scatter = |direction: &Vector3D| {
+                    let p = medium.phase.eval(&incoming, direction);
+                    (Color::new(p, p, p), p)
+                };
+                radiance = radiance + throughput * self.direct_light(&point, ray.time, scene, bvh, lights, rng, scatter, |_| point);
+
+                let direction = medium.phase.sample(&incoming, rng.next_f32(), rng.next_f32());
+                bsdf_pdf = Some(medium.phase.eval(&incoming, &direction));
+                ray = Ray::new(point, direction).with_time(ray.time);
+                if !self.survives(depth, &mut throughput, rng) {
+                    break;
+                }
+                continue;
+            }
+
+            let (hit, object) = match hit {
+                Some(hit) => hit,
+                None => break,
+            };
+            let material = hit.material.as_deref().unwrap_or(&default_material);
+
+            if material.is_emissive() {
+                let emitted = material.emitted(&hit, &-ray.direction);
+                let weight = match bsdf_pdf {
+                    Some(pdf) => power_heuristic(pdf, lights.pdf(&ray, object)),
+                    None => 1.0,
+                };
+                radiance = radiance + throughput * emitted * weight;
+            }
+
+            // Two-sided shading: flip the frame towards the viewer
+            let mut normal = material.shading_normal(&hit);
+            if normal.dot(&ray.direction) > 0.0 {
+                normal = -normal;
+            }
+            let frame = Frame::new(normal, &hit.tangent);
+            let wo = frame.to_local(&-ray.direction);
+            // Only camera rays carry differentials, later bounces read textures unfiltered
+            let footprint = texture_footprint(&ray, &hit);
+            let bsdf = material.bsdf(&hit, footprint.as_ref());
+
+            let scatter = |direction: &Vector3D| {
+                let wi = frame.to_local(direction);
+                (bsdf.eval(&wo, &wi) * wi.z.abs(), bsdf.pdf(&wo, &wi))
+            };
+            let origin = |direction: &Vector3D| offset_origin(&hit, direction);
+            radiance = radiance + throughput * self.direct_light(&hit.point, ray.time, scene, bvh, lights, rng, scatter, origin);
+
+            let sample = match bsdf.sample(&wo, rng.next_f32(), rng.next_f32(), rng.next_f32()) {
+                Some(sample) => sample,
+                None => break,
+            };
+            throughput = throughput * sample.weight;
+            bsdf_pdf = Some(sample.pdf);
+            if !self.survives(depth, &mut throughput, rng) {
+                break;
+            }
+
+            let direction = frame.to_world(&sample.wi);
+            ray = Ray::new(offset_origin(&hit, &direction), direction).with_time(ray.time);
+        }
+
+        match &scene.fog {
+            Some(fog) => fog.apply(radiance, first_distance),
+            None => radiance,
+        }
+    }
+
+    // Russian roulette once paths are a few bounces long
+    fn survives(&self, depth: u32, throughput: &mut Color, rng: &mut Rng) -> bool {
+        if depth < ROULETTE_DEPTH {
+            return true;
+        }
+        let survival = throughput.r.max(throughput.g).max(throughput.b).min(0.95);
+        if rng.next_f32() >= survival {
+            return false;
+        }
+        *throughput = *throughput * (1.0 / survival);
+        true
+    }
+
+    // Light reaching a path vertex from every point light and one sampled lamp. `scatter`
+    // gives, for a direction towards a light, the share sent on to the viewer (BSDF times
+    // cosine, or phase function) and its sampling density; `origin` where shadow rays
+    // in that direction start. Shadow rays carry the path's `time`.
+    #[allow(clippy::too_many_arguments)]
+    fn direct_light(
+        &self,
+        point: &Vector3D,
+        time: f32,
+        scene: &Scene,
+        bvh: &Bvh,
+        lights: &LightList,
+        rng: &mut Rng,
+        scatter: impl Fn(&Vector3D) -> (Color, f32),
+        origin: impl Fn(&Vector3D) -> Vector3D,
+    ) -> Color {
+        let mut result = Color::new(0.0, 0.0, 0.0);
+
+        for light in &scene.lights {
+            let light_dir = (light.position - *point).normalize();
+            let (value, _) = scatter(&light_dir);
+            let arriving = light.radiance_at(point);
+            if (value * arriving).luminance() <= 0.0 {
+                continue;
+            }
+            let shadow_origin = origin(&light_dir);
+            let distance = (light.position - shadow_origin).length();
+            let shadow_ray = Ray::new(shadow_origin, light_dir).with_time(time);
+            if bvh.occluded(&shadow_ray, distance) {
+                continue;
+            }
+            // Lights don't fall off with distance here, and are scaled by pi so a white
+            // Lambertian surface comes out as bright as with the Whitted renderer
+            let visibility = transmittance(&scene.media, &shadow_ray, distance);
+            result = result + value * arriving * (PI * visibility);
+        }
+
+        if let Some((light, sample)) = lights.sample(point, time, rng.next_f32(), rng.next_f32(), rng.next_f32()) {
+            let light_dir = (sample.point - *point).normalize();
+            let (value, pdf) = scatter(&light_dir);
+            if sample.normal.dot(&light_dir) < 0.0 && value.luminance() > 0.0 {
+                let shadow_origin = origin(&light_dir);
+                let distance = (sample.point - shadow_origin).length() - SHADOW_EPSILON;
+                let shadow_ray = Ray::new(shadow_origin, light_dir).with_time(time);
+                if !bvh.occluded(&shadow_ray, distance) {
+                    let weight = power_heuristic(sample.pdf, pdf);
+                    let visibility = transmittance(&scene.media, &shadow_ray, distance);
+                    result = result + value * light.radiance(&sample) * (visibility * weight / sample.pdf);
+                }
+            }
+        }
+
+        result
+    }
+}
+
+impl Default for PathTracer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn power_heuristic(pdf: f32, other: f32) -> f32 {
+    let (a, b) = (pdf * pdf, other * other);
+    if a + b <= 0.0 { 0.0 } else { a / (a + b) }
+}
@@ -0,0 +1,143 @@
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use image::ImageResult;
+use crate::core::color::Color;
+use crate::core::camera::Projection;
+use crate::scene::scene::Scene;
+use super::renderer::Renderer;
+use super::accumulation::Accumulation;
+use super::output::{composite_png, save_png};
+use super::checkpoint::{save_checkpoint, CheckpointKey};
+
+const PROGRESS_BAR_WIDTH: usize = 30;
+
+// Renders in passes of a few samples per pixel, writing the running average to
+// `dump_path` every `dump_interval` and a progress bar with the time left to stdout, so a
+// long render can be judged and stopped early. With checkpoints the accumulation itself
+// is saved every `checkpoint_interval`, so a stopped or crashed render can be resumed.
+pub struct Progressive {
+    pub pass_samples: u32,
+    pub dump_path: Option<PathBuf>,
+    pub dump_interval: Duration,
+    // Dumps of a cropped render are pasted into the full frame already at `dump_path`
+    pub composite: bool,
+    pub checkpoint: Option<(PathBuf, CheckpointKey)>,
+    pub checkpoint_interval: Duration,
+    pub show_progress: bool,
+}
+
+impl Progressive {
+    pub fn new() -> Self {
+        Self {
+            pass_samples: 1,
+            dump_path: None,
+            dump_interval: Duration::from_secs(10),
+            composite: false,
+            checkpoint: None,
+            checkpoint_interval: Duration::from_secs(60),
+            show_progress: true,
+        }
+    }
+
+    pub fn with_pass_samples(mut self, samples: u32) -> Self {
+        self.pass_samples = samples.max(1);
+        self
+    }
+
+    pub fn with_dumps(mut self, path: PathBuf, interval: Duration) -> Self {
+        self.dump_path = Some(path);
+        self.dump_interval = interval;
+        self
+    }
+
+    pub fn with_composite(mut self, composite: bool) -> Self {
+        self.composite = composite;
+        self
+    }
+
+    pub fn with_checkpoints(mut self, path: PathBuf, key: CheckpointKey, interval: Duration) -> Self {
+        self.checkpoint = Some((path, key));
+        self.checkpoint_interval = interval;
+        self
+    }
+
+    pub fn with_progress(mut self, show: bool) -> Self {
+        self.show_progress = show;
+        self
+    }
+
+    // Takes the renderer's samples per pixel, continuing from whatever `accumulation`
+    // already holds, e.g. one loaded from a checkpoint. The last estimate and the last
+    // checkpoint are always written.
+    pub fn render(&self, renderer: &Renderer, scene: &Scene, camera: &dyn Projection, accumulation: &mut Accumulation) -> ImageResult<Vec<Color>> {
+        let start = Instant::now();
+        let first_samples = accumulation.samples;
+        let mut last_dump = Instant::now();
+        let mut last_checkpoint = Instant::now();
+        let mut result = Ok(());
+
+        renderer.render_passes(scene, camera, accumulation, self.pass_samples, |accumulation| {
+            if self.show_progress {
+                print_progress(accumulation.samples, renderer.samples, accumulation.samples - first_samples, start.elapsed());
+            }
+            let done = accumulation.samples >= renderer.samples;
+            if let Some(path) = &self.dump_path {
+                if done || last_dump.elapsed() >= self.dump_interval {
+                    result = if self.composite {
+                        composite_png(path, &accumulation.image(), &renderer.region(), renderer.width, renderer.height)
+                    } else {
+                        save_png(path, &accumulation.image(), accumulation.width, accumulation.height)
+                    };
+                    last_dump = Instant::now();
+                }
+            }
+            if let Some((path, key)) = &self.checkpoint {
+                if result.is_ok() && (done || last_checkpoint.elapsed() >= self.checkpoint_interval) {
+                    result = save_checkpoint(path, key, accumulation).map_err(Into::into);
+                    last_checkpoint = Instant::now();
+                }
+            }
+            result.is_ok()
+        });
+        if self.show_progress {
+            println!();
+        }
+        result?;
+        Ok(accumulation.image())
+    }
+}
+
+impl Default for Progressive {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// One line, redrawn in place: [#####-----]  50%  16/32 spp  ETA 1m 05s. The time left
+// comes from the pace of the `taken` samples this run took in `elapsed`.
+fn print_progress(done: u32, total: u32, taken: u32, elapsed: Duration) {
+    let fraction = if total > 0 { done as f32 / total as f32 } else { 1.0 };
+    let filled = ((fraction * PROGRESS_BAR_WIDTH as f32) as usize).min(PROGRESS_BAR_WIDTH);
+    let left = total.saturating_sub(done);
+    let eta = if taken > 0 { elapsed.as_secs_f32() * left as f32 / taken as f32 } else { 0.0 };
+    print!(
+        "\r[{}{}] {:3.0}%  {}/{} spp  ETA {}",
+        "#".repeat(filled),
+        "-".repeat(PROGRESS_BAR_WIDTH - filled),
+        fraction * 100.0,
+        done,
+        total,
+        format_duration(eta),
+    );
+    let _ = io::stdout().flush();
+}
+
+fn format_duration(seconds: f32) -> String {
+    let seconds = seconds.round() as u64;
+    match seconds {
+        0..=59 => format!("{}s", seconds),
+        60..=3599 => format!("{}m {:02}s", seconds / 60, seconds % 60),
+        _ => format!("{}h {:02}m", seconds / 3600, seconds % 3600 / 60),
+    }
+}
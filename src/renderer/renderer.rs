@@ -1,80 +1,297 @@
+use std::f32::consts::PI;
 use crate::math::vector3d::Vector3D;
 use crate::core::color::Color;
-use crate::core::ray::Ray;
+use crate::core::ray::{Ray, RayDifferential};
+use crate::core::rng::Rng;
 use crate::scene::light::Light;
 use crate::scene::scene::Scene;
-use crate::core::camera::Camera;
+use crate::scene::bvh::Bvh;
+use crate::scene::emitters::LightList;
+use crate::core::camera::Projection;
+use crate::cameras::stereo::{Eye, StereoLayout, StereoRig};
+use crate::materials::material::Material;
 use crate::objects::object::{Intersection, Object};
-use std::f32::INFINITY;
+use crate::objects::instance::Instance;
+use crate::textures::texture::Footprint;
+use crate::media::medium::transmittance;
+use super::path_tracer::PathTracer;
+use super::accumulation::Accumulation;
+use super::crop::{CropWindow, Region};
+
+const SHADOW_BIAS: f32 = 1e-3;
+// Fixed steps the Whitted renderer marches through media, and how far it goes when the
+// ray hits nothing
+const MEDIA_STEPS: u32 = 64;
+const MAX_MARCH_DISTANCE: f32 = 100.0;
+
+pub enum Integrator {
+    // Point lights, ambient term and mirror reflections
+    Whitted,
+    PathTracer(PathTracer),
+}
+
+// A scene made ready to trace: the BVH over its objects and flattened instances, and the
+// lamps to sample. Building these is most of the cost of rendering a few pixels, so one
+// can be shared by several renders of the same scene, such as the tiles of a frame.
+pub struct PreparedScene<'a> {
+    scene: &'a Scene,
+    bvh: Bvh<'a>,
+    lights: LightList<'a>,
+}
+
+impl<'a> PreparedScene<'a> {
+    // `instances` is `scene.root.flatten()`
+    pub fn new(scene: &'a Scene, instances: &'a [Instance]) -> Self {
+        let bvh = Bvh::new(
+            scene.objects.iter()
+                .map(|object| object.as_ref())
+                .chain(instances.iter().map(|instance| instance as &dyn Object)),
+        );
+        Self { scene, bvh, lights: LightList::new(instances, &scene.objects) }
+    }
+}
 
 pub struct Renderer {
     pub width: u32,
     pub height: u32,
+    pub integrator: Integrator,
+    // Rays per pixel, each with its own position in the pixel and on the lens. A single
+    // Whitted sample goes through the pixel corner and the lens centre.
+    pub samples: u32,
+    pub seed: u64,
+    // Only this part of the frame is rendered when set
+    pub crop: Option<CropWindow>,
 }
 
 impl Renderer {
     pub fn new(width: u32, height: u32) -> Self {
-        Self { width, height }
+        Self { width, height, integrator: Integrator::Whitted, samples: 1, seed: 0, crop: None }
+    }
+
+    pub fn with_integrator(mut self, integrator: Integrator) -> Self {
+        self.integrator = integrator;
+        self
+    }
+
+    pub fn with_samples(mut self, samples: u32) -> Self {
+        self.samples = samples.max(1);
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
     }
 
-    pub fn render(&self, scene: &Scene, camera: &Camera) -> Vec<Color> {
-        let mut image = vec![Color::new(0.0, 0.0, 0.0); (self.width * self.height) as usize];
+    pub fn with_crop(mut self, crop: CropWindow) -> Self {
+        self.crop = Some(crop);
+        self
+    }
+
+    // Pixels rendered: the crop window, or the whole frame
+    pub fn region(&self) -> Region {
+        match &self.crop {
+            Some(crop) => crop.region(self.width, self.height),
+            None => Region::full(self.width, self.height),
+        }
+    }
 
-        for y in 0..self.height {
-            for x in (0..self.width).rev() {
-                let u = x as f32 / self.width as f32;
-                let v = y as f32 / self.height as f32;
-                let ray = camera.generate_ray(u, v);
-                let color = self.trace_ray(ray, scene, 0);
-                image[(y * self.width + x) as usize] = color;
+    // An empty accumulation for the pixels this renderer renders
+    pub fn accumulation(&self) -> Accumulation {
+        Accumulation::for_region(&self.region(), self.width, self.seed)
+    }
+
+    // The image of `region()`, as large as the crop window when there is one. Cropped
+    // pixels match the same pixels of a full render exactly.
+    pub fn render(&self, scene: &Scene, camera: &dyn Projection) -> Vec<Color> {
+        let instances = scene.root.flatten();
+        self.render_prepared(&PreparedScene::new(scene, &instances), camera)
+    }
+
+    // As `render`, for a scene already prepared
+    pub fn render_prepared(&self, prepared: &PreparedScene, camera: &dyn Projection) -> Vec<Color> {
+        let mut accumulation = self.accumulation();
+        self.add_passes(prepared, camera, &mut accumulation, self.samples, |_| true);
+        accumulation.image()
+    }
+
+    // Renders the crop window over the same pixels of `image`, a previous full render
+    pub fn render_into(&self, scene: &Scene, camera: &dyn Projection, image: &mut [Color]) {
+        let region = self.region();
+        region.composite(&self.render(scene, camera), image, self.width);
+    }
+
+    // Adds passes of `pass_samples` samples per pixel to `accumulation` until it holds
+    // `samples` in total, or `after_pass` asks to stop by returning false. `accumulation`
+    // covers `region()`.
+    pub fn render_passes(
+        &self,
+        scene: &Scene,
+        camera: &dyn Projection,
+        accumulation: &mut Accumulation,
+        pass_samples: u32,
+        after_pass: impl FnMut(&Accumulation) -> bool,
+    ) {
+        let instances = scene.root.flatten();
+        self.add_passes(&PreparedScene::new(scene, &instances), camera, accumulation, pass_samples, after_pass);
+    }
+
+    fn add_passes(
+        &self,
+        prepared: &PreparedScene,
+        camera: &dyn Projection,
+        accumulation: &mut Accumulation,
+        pass_samples: u32,
+        mut after_pass: impl FnMut(&Accumulation) -> bool,
+    ) {
+        let PreparedScene { scene, bvh, lights } = prepared;
+        let pixel_width = 1.0 / self.width as f32;
+        let pixel_height = 1.0 / self.height as f32;
+        let (shutter_open, shutter_close) = camera.shutter();
+        let jitter = self.samples > 1 || matches!(self.integrator, Integrator::PathTracer(_));
+        let region = self.region();
+        assert_eq!((accumulation.width, accumulation.height), (region.width, region.height), "accumulation does not match the render region");
+
+        while accumulation.samples < self.samples {
+            let samples = pass_samples.clamp(1, self.samples - accumulation.samples);
+            for y in region.y..region.y + region.height {
+                for x in (region.x..region.x + region.width).rev() {
+                    let index = ((y - region.y) * region.width + (x - region.x)) as usize;
+                    let mut rng = accumulation.rngs[index];
+                    let mut sum = accumulation.sums[index];
+                    for _ in 0..samples {
+                        let (offset_x, offset_y, lens) = if jitter {
+                            (rng.next_f32(), rng.next_f32(), (rng.next_f32(), rng.next_f32()))
+                        } else {
+                            (0.0, 0.0, (0.5, 0.5))
+                        };
+                        let u = (x as f32 + offset_x) / self.width as f32;
+                        let v = (y as f32 + offset_y) / self.height as f32;
+                        let mut ray = match camera.generate_ray_differential(u, v, pixel_width, pixel_height, lens) {
+                            Some(ray) => ray,
+                            None => continue,
+                        };
+                        ray.time = shutter_open;
+                        if jitter && shutter_close > shutter_open {
+                            ray.time += (shutter_close - shutter_open) * rng.next_f32();
+                        }
+                        sum = sum + match &self.integrator {
+                            Integrator::Whitted => self.trace_ray(ray, prepared, &mut rng, 0),
+                            Integrator::PathTracer(tracer) => tracer.radiance(ray, scene, bvh, lights, &mut rng),
+                        };
+                    }
+                    accumulation.sums[index] = sum;
+                    accumulation.rngs[index] = rng;
+                }
+            }
+            accumulation.samples += samples;
+            if !after_pass(accumulation) {
+                break;
             }
         }
+    }
 
-        image
+    // Renders both eyes of the rig at the renderer's size (or crop) each and combines them; see
+    // `StereoLayout::image_size` for the size of the result
+    pub fn render_stereo(&self, scene: &Scene, rig: &StereoRig, layout: StereoLayout) -> Vec<Color> {
+        let instances = scene.root.flatten();
+        let prepared = PreparedScene::new(scene, &instances);
+        let left = self.render_prepared(&prepared, &rig.eye(Eye::Left));
+        let right = self.render_prepared(&prepared, &rig.eye(Eye::Right));
+        let region = self.region();
+        layout.combine(&left, &right, region.width, region.height)
     }
 
-    fn trace_ray(&self, ray: Ray, scene: &Scene, depth: u32) -> Color {
+    fn trace_ray(&self, ray: Ray, prepared: &PreparedScene, rng: &mut Rng, depth: u32) -> Color {
         if depth > 5 {
             return Color::new(0.0, 0.0, 0.0);
         }
 
-        let clone_ray = ray.clone();
-
-        if let Some((intersection, object)) = self.nearest_intersection(ray, scene) {
-
-            self.shade(clone_ray, scene, &intersection, object, depth)
+        let PreparedScene { scene, bvh, .. } = prepared;
+        let hit = bvh.intersect(&ray);
+        let mut color = if let Some(intersection) = &hit {
+            self.shade(&ray, prepared, rng, intersection, depth)
         } else {
             Color::new(0.0, 0.0, 0.0) // Background color
+        };
+
+        let distance = hit.as_ref().map_or(f32::INFINITY, |hit| hit.t);
+        if !scene.media.is_empty() {
+            let (scattered, visibility) = self.march_media(&ray, distance, scene, bvh);
+            color = color * visibility + scattered;
         }
+        if let Some(fog) = &scene.fog {
+            color = fog.apply(color, distance);
+        }
+        color
     }
 
-    fn nearest_intersection<'a>(&self, ray: Ray, scene: &'a Scene) -> Option<(Intersection, &'a Box<dyn Object>)> {
-        let mut nearest: Option<(Intersection, &Box<dyn Object>)> = None;
-        let mut min_distance = INFINITY;
+    // Single scattering of the point and spot lights along the ray, in fixed steps: the
+    // light the media send towards the viewer (light shafts where shadows cut through
+    // them) and the share of the light from behind that gets through.
+    fn march_media(&self, ray: &Ray, distance: f32, scene: &Scene, bvh: &Bvh) -> (Color, f32) {
+        let range = scene.media
+            .iter()
+            .filter_map(|medium| medium.segment(ray, distance.min(MAX_MARCH_DISTANCE)))
+            .reduce(|a, b| (a.0.min(b.0), a.1.max(b.1)));
+        let (start, end) = match range {
+            Some(range) => range,
+            None => return (Color::new(0.0, 0.0, 0.0), 1.0),
+        };
 
-        for object in &scene.objects {
-            if let Some(intersection) = object.intersect(&ray) {
-                if intersection.t < min_distance {
-                    min_distance = intersection.t;
-                    nearest = Some((intersection, object));
+        let step = (end - start) / MEDIA_STEPS as f32;
+        let mut scattered = Color::new(0.0, 0.0, 0.0);
+        let mut visibility = 1.0;
+        for i in 0..MEDIA_STEPS {
+            let point = ray.point_at(start + (i as f32 + 0.5) * step);
+            let mut extinction = 0.0;
+            for medium in &scene.media {
+                let density = medium.density_at(&point);
+                if density <= 0.0 {
+                    continue;
+                }
+                extinction += medium.extinction() * density;
+
+                for light in &scene.lights {
+                    let arriving = light.radiance_at(&point);
+                    if arriving.luminance() <= 0.0 {
+                        continue;
+                    }
+                    let to_light = light.position - point;
+                    let light_distance = to_light.length();
+                    let light_dir = to_light * (1.0 / light_distance);
+                    let shadow_ray = Ray::new(point, light_dir).with_time(ray.time);
+                    if bvh.occluded(&shadow_ray, light_distance) {
+                        continue;
+                    }
+                    // Light travels away from the light, then scatters back along the ray towards the eye
+                    let phase = medium.phase.eval(&-light_dir, &-ray.direction);
+                    let reaching = transmittance(&scene.media, &shadow_ray, light_distance);
+                    let strength = medium.scattering * density * phase * PI * reaching * visibility * step;
+                    scattered = scattered + medium.color * arriving * strength;
                 }
             }
+            visibility *= (-extinction * step).exp();
         }
-
-        nearest
+        (scattered, visibility)
     }
 
-    fn shade(&self, ray: Ray, scene: &Scene, intersection: &Intersection, object: &Box<dyn Object>, depth: u32) -> Color {
+    fn shade(&self, ray: &Ray, prepared: &PreparedScene, rng: &mut Rng, intersection: &Intersection, depth: u32) -> Color {
+        let PreparedScene { scene, bvh, lights } = prepared;
+        let default_material = Material::default();
+        let material = intersection.material.as_deref().unwrap_or(&default_material);
+        let normal = material.shading_normal(intersection);
         let mut color = Color::new(0.1, 0.1, 0.1); // Ambient light
 
         for light in &scene.lights {
             let light_dir = (light.position - intersection.point).normalize();
-            let shadow_ray = Ray::new(intersection.point + light_dir * 0.1, light_dir);
+            let shadow_ray = Ray::new(offset_origin(intersection, &light_dir), light_dir).with_time(ray.time);
 
-            if !self.is_in_shadow(shadow_ray, scene, light) {
-                let normal = object.normal(&intersection.point);
+            if !self.is_in_shadow(&shadow_ray, bvh, light) {
                 let diffuse = normal.dot(&light_dir).max(0.0);
-                color = color + light.color * light.intensity * diffuse;
+                let distance = (light.position - shadow_ray.origin).length();
+                let visibility = transmittance(&scene.media, &shadow_ray, distance);
+                color = color + light.radiance_at(&intersection.point) * (diffuse * visibility);
 
                 // Add specular highlight
                 // let view_dir = (ray.origin - intersection.point).normalize();
@@ -84,17 +301,139 @@ impl Renderer {
             }
         }
 
+        // One point on each lamp. Its emission over the solid angle the point stands for
+        // lights the surface, divided by pi as for point lights, so a lamp looks the same
+        // here as under the path tracer.
+        for light in &lights.lights {
+            let sample = match light.object.sample_light_at(&intersection.point, ray.time, rng.next_f32(), rng.next_f32()) {
+                Some(sample) if sample.pdf > 0.0 => sample,
+                _ => continue,
+            };
+            let light_dir = (sample.point - intersection.point).normalize();
+            let diffuse = normal.dot(&light_dir);
+            if diffuse <= 0.0 || sample.normal.dot(&light_dir) >= 0.0 {
+                continue;
+            }
+            let shadow_ray = Ray::new(offset_origin(intersection, &light_dir), light_dir).with_time(ray.time);
+            let distance = (sample.point - shadow_ray.origin).length() - SHADOW_BIAS;
+            if bvh.occluded(&shadow_ray, distance) {
+                continue;
+            }
+            let visibility = transmittance(&scene.media, &shadow_ray, distance);
+            color = color + light.radiance(&sample) * (diffuse * visibility / (PI * sample.pdf));
+        }
+
+        let (u, v) = intersection.uv;
+        let albedo = match texture_footprint(ray, intersection) {
+            Some(footprint) => material.albedo.filtered_value(u, v, &intersection.point, &footprint),
+            None => material.albedo.value(u, v, &intersection.point),
+        };
+        color = color * albedo + material.emitted(intersection, &-ray.direction);
+
+        if material.reflectivity > 0.0 {
+            let reflected = self.reflected_ray(ray, intersection, &normal);
+            let reflection = self.trace_ray(reflected, prepared, rng, depth + 1);
+            color = color * (1.0 - material.reflectivity) + reflection * material.reflectivity;
+        }
+
         color
     }
 
-    fn is_in_shadow(&self, shadow_ray: Ray, scene: &Scene, light: &Light) -> bool {
-        for object in &scene.objects {
-            if let Some(intersection) = object.intersect(&shadow_ray) {
-                if intersection.t < (light.position - shadow_ray.origin).length() {
-                    return true;
-                }
-            }
+    // Mirror ray off the surface. Differentials are reflected off the tangent plane with
+    // the same normal, ignoring the surface's curvature.
+    fn reflected_ray(&self, ray: &Ray, intersection: &Intersection, normal: &Vector3D) -> Ray {
+        let direction = ray.direction.reflect(normal);
+        let reflected = Ray::new(offset_origin(intersection, &direction), direction).with_time(ray.time);
+
+        let differential = ray.differential.as_ref().and_then(|d| {
+            let geometric = intersection.normal.normalize();
+            let rx_origin = tangent_plane_hit(intersection, &geometric, &d.rx_origin, &d.rx_direction)?;
+            let ry_origin = tangent_plane_hit(intersection, &geometric, &d.ry_origin, &d.ry_direction)?;
+            Some(RayDifferential {
+                rx_origin,
+                rx_direction: d.rx_direction.reflect(normal).normalize(),
+                ry_origin,
+                ry_direction: d.ry_direction.reflect(normal).normalize(),
+            })
+        });
+
+        match differential {
+            Some(differential) => reflected.with_differential(differential),
+            None => reflected,
         }
-        false
     }
-}
\ No newline at end of file
+
+    fn is_in_shadow(&self, shadow_ray: &Ray, bvh: &Bvh, light: &Light) -> bool {
+        let distance = (light.position - shadow_ray.origin).length();
+        bvh.occluded(shadow_ray, distance)
+    }
+}
+
+// Secondary rays start slightly off the surface, on the side they leave towards,
+// along the geometric normal so bump or normal maps can't push them inside.
+pub(crate) fn offset_origin(intersection: &Intersection, direction: &Vector3D) -> Vector3D {
+    let normal = intersection.normal.normalize();
+    let side = if normal.dot(direction) >= 0.0 { 1.0 } else { -1.0 };
+    intersection.point + normal * (SHADOW_BIAS * side)
+}
+
+// Where an offset ray meets the plane tangent to the surface at the hit
+fn tangent_plane_hit(intersection: &Intersection, normal: &Vector3D, origin: &Vector3D, direction: &Vector3D) -> Option<Vector3D> {
+    let denom = normal.dot(direction);
+    if denom.abs() < 1e-8 {
+        return None;
+    }
+    let t = normal.dot(&(intersection.point - *origin)) / denom;
+    Some(*origin + *direction * t)
+}
+
+// Texture-space extent of the pixel: the differential rays' hits on the tangent plane
+// expressed in the surface's (u, v) tangent frame by least squares.
+pub(crate) fn texture_footprint(ray: &Ray, intersection: &Intersection) -> Option<Footprint> {
+    let d = ray.differential.as_ref()?;
+    let normal = intersection.normal.normalize();
+    let dpdx = tangent_plane_hit(intersection, &normal, &d.rx_origin, &d.rx_direction)? - intersection.point;
+    let dpdy = tangent_plane_hit(intersection, &normal, &d.ry_origin, &d.ry_direction)? - intersection.point;
+
+    let (a, b) = (intersection.tangent, intersection.bitangent);
+    let (aa, ab, bb) = (a.dot(&a), a.dot(&b), b.dot(&b));
+    let determinant = aa * bb - ab * ab;
+    if determinant.abs() < 1e-12 {
+        return None;
+    }
+    let solve = |dp: &Vector3D| {
+        let (ra, rb) = (a.dot(dp), b.dot(dp));
+        ((bb * ra - ab * rb) / determinant, (aa * rb - ab * ra) / determinant)
+    };
+
+    let (dudx, dvdx) = solve(&dpdx);
+    let (dudy, dvdy) = solve(&dpdy);
+    Some(Footprint { dudx, dvdx, dudy, dvdy })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::aabb::Aabb;
+    use crate::media::medium::Medium;
+
+    // Light gathered by a ray from (0, 0, 1) along +z through a slab of forward-scattering
+    // haze around (0, 0, 5), lit by one point light
+    fn haze_lit_from(light: Vector3D) -> Color {
+        let mut scene = Scene::new();
+        let bounds = Aabb::new(Vector3D::new(-1.0, -1.0, 4.0), Vector3D::new(1.0, 1.0, 6.0));
+        scene.add_medium(Medium::homogeneous(0.0, 0.5).with_anisotropy(0.8).with_bounds(bounds));
+        scene.add_light(Light::new(light, Color::new(1.0, 1.0, 1.0), 1.0));
+        let bvh = Bvh::new(std::iter::empty());
+        let ray = Ray::new(Vector3D::new(0.0, 0.0, 1.0), Vector3D::new(0.0, 0.0, 1.0));
+        Renderer::new(1, 1).march_media(&ray, f32::INFINITY, &scene, &bvh).0
+    }
+
+    #[test]
+    fn forward_scattering_haze_glows_towards_the_light() {
+        // The same distance from the haze, beyond it and behind the viewer
+        let facing = haze_lit_from(Vector3D::new(0.0, 0.0, 10.0)).luminance();
+        let behind = haze_lit_from(Vector3D::new(0.0, 0.0, 0.0)).luminance();
+        assert!(facing > 10.0 * behind, "facing {} behind {}", facing, behind);
+    }
+}
@@ -0,0 +1,159 @@
+use crate::math::aabb::Aabb;
+use crate::core::ray::Ray;
+use crate::objects::object::{Intersection, Object};
+
+const MAX_LEAF_SIZE: usize = 2;
+
+enum BvhNode {
+    Leaf { bounds: Aabb, start: usize, end: usize },
+    Interior { bounds: Aabb, left: usize, right: usize },
+}
+
+impl BvhNode {
+    fn bounds(&self) -> &Aabb {
+        match self {
+            BvhNode::Leaf { bounds, .. } | BvhNode::Interior { bounds, .. } => bounds,
+        }
+    }
+}
+
+// Bounding volume hierarchy over borrowed scene objects, built once per render.
+// Objects without bounds (planes) are kept aside and tested against every ray.
+pub struct Bvh<'a> {
+    objects: Vec<&'a dyn Object>,
+    nodes: Vec<BvhNode>,
+    unbounded: Vec<&'a dyn Object>,
+}
+
+impl<'a> Bvh<'a> {
+    pub fn new<I>(objects: I) -> Self
+    where
+        I: IntoIterator<Item = &'a dyn Object>,
+    {
+        let mut bounded = Vec::new();
+        let mut unbounded = Vec::new();
+        for object in objects {
+            match object.bounds() {
+                Some(bounds) => bounded.push((bounds, object)),
+                None => unbounded.push(object),
+            }
+        }
+
+        let mut bvh = Self { objects: Vec::with_capacity(bounded.len()), nodes: Vec::new(), unbounded };
+        if !bounded.is_empty() {
+            bvh.build(&mut bounded);
+        }
+        bvh
+    }
+
+    fn build(&mut self, items: &mut [(Aabb, &'a dyn Object)]) -> usize {
+        let bounds = items.iter().fold(Aabb::empty(), |acc, (b, _)| acc.union(b));
+
+        if items.len() <= MAX_LEAF_SIZE {
+            let start = self.objects.len();
+            self.objects.extend(items.iter().map(|(_, object)| *object));
+            self.nodes.push(BvhNode::Leaf { bounds, start, end: self.objects.len() });
+            return self.nodes.len() - 1;
+        }
+
+        // Median split along the longest axis of the centroids
+        let centroids = items.iter().fold(Aabb::empty(), |acc, (b, _)| acc.include(&b.centroid()));
+        let axis = centroids.longest_axis();
+        items.sort_by(|(a, _), (b, _)| {
+            a.centroid().component(axis)
+                .partial_cmp(&b.centroid().component(axis))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        let index = self.nodes.len();
+        self.nodes.push(BvhNode::Leaf { bounds, start: 0, end: 0 });
+        let (left_items, right_items) = items.split_at_mut(items.len() / 2);
+        let left = self.build(left_items);
+        let right = self.build(right_items);
+        self.nodes[index] = BvhNode::Interior { bounds, left, right };
+        index
+    }
+
+    fn enters(bounds: &Aabb, ray: &Ray, max_t: f32) -> bool {
+        match bounds.intersect(ray) {
+            Some((t_enter, t_exit)) => t_exit >= 0.0 && t_enter <= max_t,
+            None => false,
+        }
+    }
+
+    pub fn intersect(&self, ray: &Ray) -> Option<Intersection> {
+        self.intersect_object(ray).map(|(intersection, _)| intersection)
+    }
+
+    // Nearest hit along with the object that was hit
+    pub fn intersect_object(&self, ray: &Ray) -> Option<(Intersection, &'a dyn Object)> {
+        let mut nearest: Option<(Intersection, &'a dyn Object)> = None;
+        let mut min_distance = f32::INFINITY;
+
+        let test = |object: &'a dyn Object, nearest: &mut Option<(Intersection, &'a dyn Object)>, min_distance: &mut f32| {
+            if let Some(intersection) = object.intersect(ray) {
+                if intersection.t < *min_distance {
+                    *min_distance = intersection.t;
+                    *nearest = Some((intersection, object));
+                }
+            }
+        };
+
+        for object in &self.unbounded {
+            test(*object, &mut nearest, &mut min_distance);
+        }
+
+        let mut stack = if self.nodes.is_empty() { Vec::new() } else { vec![0] };
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if !Self::enters(node.bounds(), ray, min_distance) {
+                continue;
+            }
+            match node {
+                BvhNode::Leaf { start, end, .. } => {
+                    for object in &self.objects[*start..*end] {
+                        test(*object, &mut nearest, &mut min_distance);
+                    }
+                }
+                BvhNode::Interior { left, right, .. } => {
+                    stack.push(*right);
+                    stack.push(*left);
+                }
+            }
+        }
+
+        nearest
+    }
+
+    // Whether anything is hit closer than `max_distance` along the ray
+    pub fn occluded(&self, ray: &Ray, max_distance: f32) -> bool {
+        let blocks = |object: &dyn Object| {
+            object.intersect(ray).is_some_and(|intersection| intersection.t < max_distance)
+        };
+
+        if self.unbounded.iter().any(|object| blocks(*object)) {
+            return true;
+        }
+
+        let mut stack = if self.nodes.is_empty() { Vec::new() } else { vec![0] };
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if !Self::enters(node.bounds(), ray, max_distance) {
+                continue;
+            }
+            match node {
+                BvhNode::Leaf { start, end, .. } => {
+                    if self.objects[*start..*end].iter().any(|object| blocks(*object)) {
+                        return true;
+                    }
+                }
+                BvhNode::Interior { left, right, .. } => {
+                    stack.push(*right);
+                    stack.push(*left);
+                }
+            }
+        }
+
+        false
+    }
+}
@@ -0,0 +1,66 @@
+use crate::math::vector3d::Vector3D;
+use crate::core::color::Color;
+use crate::core::ray::Ray;
+use crate::materials::material::Material;
+use crate::objects::object::{LightSample, Object};
+use crate::objects::instance::Instance;
+
+// A piece of scene geometry whose material glows
+pub struct AreaLight<'a> {
+    pub object: &'a dyn Object,
+    pub material: &'a Material,
+}
+
+impl AreaLight<'_> {
+    // Radiance the lamp gives off at a point sampled on it
+    pub fn radiance(&self, sample: &LightSample) -> Color {
+        let (u, v) = sample.uv;
+        self.material.emission_at(u, v, &sample.point)
+    }
+}
+
+// Every emissive object of the scene, from the node tree or added straight to its
+// objects, gathered at render time so lamps light the scene without a matching `Light`
+// entry. Lamps are picked with equal odds.
+pub struct LightList<'a> {
+    pub lights: Vec<AreaLight<'a>>,
+}
+
+impl<'a> LightList<'a> {
+    pub fn new(instances: &'a [Instance], objects: &'a [Box<dyn Object>]) -> Self {
+        let lights = instances
+            .iter()
+            .map(|instance| instance as &dyn Object)
+            .chain(objects.iter().map(|object| object.as_ref()))
+            .filter_map(|object| Some(AreaLight { object, material: object.emission()? }))
+            .collect();
+        Self { lights }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lights.is_empty()
+    }
+
+    // One lamp, placed as it is at `time`, and a point on it; the pdf includes the odds of
+    // picking that lamp
+    pub fn sample(&self, reference: &Vector3D, time: f32, u: f32, u1: f32, u2: f32) -> Option<(&AreaLight<'a>, LightSample)> {
+        if self.lights.is_empty() {
+            return None;
+        }
+        let count = self.lights.len();
+        let light = &self.lights[((u * count as f32) as usize).min(count - 1)];
+        let mut sample = light.object.sample_light_at(reference, time, u1, u2)?;
+        sample.pdf /= count as f32;
+        Some((light, sample))
+    }
+
+    // Density with which `sample` would have produced the ray's direction towards `hit`,
+    // the object the ray hit first; 0 unless it is one of the lamps
+    pub fn pdf(&self, ray: &Ray, hit: &dyn Object) -> f32 {
+        let hit = hit as *const dyn Object as *const ();
+        self.lights
+            .iter()
+            .find(|light| std::ptr::eq(light.object as *const dyn Object as *const (), hit))
+            .map_or(0.0, |light| light.object.light_pdf(ray) / self.lights.len() as f32)
+    }
+}
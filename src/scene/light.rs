@@ -1,14 +1,55 @@
 use crate::math::vector3d::Vector3D;
 use crate::core::color::Color;
 
+// Cone a spot light shines into: full strength inside `inner_angle`, fading out to
+// nothing at `outer_angle` (half-angles in degrees)
+#[derive(Debug, Copy, Clone)]
+pub struct Spot {
+    pub direction: Vector3D,
+    pub inner_angle: f32,
+    pub outer_angle: f32,
+}
+
 pub struct Light {
     pub position: Vector3D,
     pub color: Color,
     pub intensity: f32,
+    // None for a point light shining everywhere
+    pub spot: Option<Spot>,
 }
 
 impl Light {
     pub fn new(position: Vector3D, color: Color, intensity: f32) -> Self {
-        Self { position, color, intensity }
+        Self { position, color, intensity, spot: None }
     }
-}
\ No newline at end of file
+
+    pub fn spot(position: Vector3D, direction: Vector3D, inner_angle: f32, outer_angle: f32, color: Color, intensity: f32) -> Self {
+        let spot = Spot {
+            direction: direction.normalize(),
+            inner_angle: inner_angle.min(outer_angle),
+            outer_angle,
+        };
+        Self { spot: Some(spot), ..Self::new(position, color, intensity) }
+    }
+
+    // Fraction of the light reaching `point`, 0 outside a spot light's cone
+    pub fn attenuation(&self, point: &Vector3D) -> f32 {
+        let spot = match &self.spot {
+            Some(spot) => spot,
+            None => return 1.0,
+        };
+        let cos_angle = (*point - self.position).normalize().dot(&spot.direction);
+        let cos_inner = spot.inner_angle.to_radians().cos();
+        let cos_outer = spot.outer_angle.to_radians().cos();
+        if cos_inner - cos_outer <= 1e-6 {
+            return if cos_angle >= cos_outer { 1.0 } else { 0.0 };
+        }
+        let x = ((cos_angle - cos_outer) / (cos_inner - cos_outer)).clamp(0.0, 1.0);
+        x * x * (3.0 - 2.0 * x)
+    }
+
+    // Colour and strength arriving at `point`, before shadowing
+    pub fn radiance_at(&self, point: &Vector3D) -> Color {
+        self.color * (self.intensity * self.attenuation(point))
+    }
+}
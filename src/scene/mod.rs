@@ -1,2 +1,7 @@
 pub mod light;
-pub mod scene;
\ No newline at end of file
+#[allow(clippy::module_inception)]
+pub mod scene;
+pub mod bvh;
+pub mod node;
+pub mod emitters;
+pub mod scene_file;
@@ -0,0 +1,131 @@
+use std::sync::Arc;
+use crate::math::transform::Transform;
+use crate::math::motion::Motion;
+use crate::materials::material::Material;
+use crate::objects::object::Object;
+use crate::objects::instance::Instance;
+
+// A scene graph node: an optional piece of geometry plus child nodes, all placed by the
+// node's transform relative to its parent. A node without its own material inherits
+// the closest ancestor's. A node with `motion` moves over time, its keys taking the
+// place of `transform`; everything below it moves along.
+pub struct Node {
+    pub name: String,
+    pub transform: Transform,
+    pub motion: Option<Motion>,
+    pub material: Option<Arc<Material>>,
+    pub geometry: Option<Arc<dyn Object>>,
+    pub children: Vec<Node>,
+}
+
+impl Node {
+    pub fn group(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            transform: Transform::identity(),
+            motion: None,
+            material: None,
+            geometry: None,
+            children: Vec::new(),
+        }
+    }
+
+    pub fn geometry(name: &str, geometry: Arc<dyn Object>) -> Self {
+        Self { geometry: Some(geometry), ..Self::group(name) }
+    }
+
+    pub fn with_transform(mut self, transform: Transform) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    pub fn with_motion(mut self, motion: Motion) -> Self {
+        self.motion = Some(motion);
+        self
+    }
+
+    // Transform relative to the parent at `time`
+    pub fn transform_at(&self, time: f32) -> Transform {
+        match &self.motion {
+            Some(motion) if !motion.is_empty() => motion.at(time),
+            _ => self.transform,
+        }
+    }
+
+    pub fn with_material(mut self, material: Arc<Material>) -> Self {
+        self.material = Some(material);
+        self
+    }
+
+    pub fn with_child(mut self, child: Node) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    pub fn add_child(&mut self, child: Node) {
+        self.children.push(child);
+    }
+
+    // Descendant addressed by child names separated by '/', e.g. "table/leg_2"
+    pub fn find(&self, path: &str) -> Option<&Node> {
+        path.split('/')
+            .filter(|segment| !segment.is_empty())
+            .try_fold(self, |node, segment| node.children.iter().find(|child| child.name == segment))
+    }
+
+    pub fn find_mut(&mut self, path: &str) -> Option<&mut Node> {
+        path.split('/')
+            .filter(|segment| !segment.is_empty())
+            .try_fold(self, |node, segment| node.children.iter_mut().find(|child| child.name == segment))
+    }
+
+    // World-space instances for every piece of geometry below this node, ready for the BVH
+    pub fn flatten(&self) -> Vec<Instance> {
+        let mut instances = Vec::new();
+        self.flatten_into(&Transform::identity(), None, None, &mut instances);
+        instances
+    }
+
+    // A moving node's world motion is keyed at its own and its moving ancestors' key times
+    fn flatten_into(
+        &self,
+        parent: &Transform,
+        parent_motion: Option<&Motion>,
+        inherited: Option<&Arc<Material>>,
+        instances: &mut Vec<Instance>,
+    ) {
+        let own_motion = self.motion.as_ref().filter(|motion| !motion.is_empty());
+        let world_motion = if own_motion.is_some() || parent_motion.is_some() {
+            let mut times: Vec<f32> = own_motion.into_iter().chain(parent_motion).flat_map(|m| m.times()).collect();
+            times.sort_by(f32::total_cmp);
+            times.dedup();
+            let parent_at = |time: f32| parent_motion.map_or(*parent, |motion| motion.at(time));
+            let motion = times
+                .into_iter()
+                .fold(Motion::new(), |motion, time| motion.with_key(time, self.transform_at(time).then(&parent_at(time))));
+            Some(motion)
+        } else {
+            None
+        };
+        let world = match &world_motion {
+            Some(motion) => motion.at(0.0),
+            None => self.transform.then(parent),
+        };
+        let material = self.material.as_ref().or(inherited);
+
+        if let Some(geometry) = &self.geometry {
+            let mut instance = Instance::new(geometry.clone(), world);
+            if let Some(material) = material {
+                instance = instance.with_material(material.clone());
+            }
+            if let Some(motion) = &world_motion {
+                instance = instance.with_motion(motion.clone());
+            }
+            instances.push(instance);
+        }
+
+        for child in &self.children {
+            child.flatten_into(&world, world_motion.as_ref(), material, instances);
+        }
+    }
+}
@@ -1,9 +1,16 @@
 use crate::objects::object::Object;
 use crate::scene::light::Light;
+use crate::scene::node::Node;
+use crate::media::fog::Fog;
+use crate::media::medium::Medium;
 
 pub struct Scene {
     pub objects: Vec<Box<dyn Object>>,
     pub lights: Vec<Light>,
+    pub root: Node,
+    // Participating media the light travels through, and optional cheap depth fog
+    pub media: Vec<Medium>,
+    pub fog: Option<Fog>,
 }
 
 impl Scene {
@@ -11,6 +18,9 @@ impl Scene {
         Self {
             objects: Vec::new(),
             lights: Vec::new(),
+            root: Node::group("root"),
+            media: Vec::new(),
+            fog: None,
         }
     }
 
@@ -21,4 +31,30 @@ impl Scene {
     pub fn add_light(&mut self, light: Light) {
         self.lights.push(light);
     }
-}
\ No newline at end of file
+
+    pub fn add_medium(&mut self, medium: Medium) {
+        self.media.push(medium);
+    }
+
+    pub fn set_fog(&mut self, fog: Fog) {
+        self.fog = Some(fog);
+    }
+
+    pub fn add_node(&mut self, node: Node) {
+        self.root.add_child(node);
+    }
+
+    pub fn node(&self, path: &str) -> Option<&Node> {
+        self.root.find(path)
+    }
+
+    pub fn node_mut(&mut self, path: &str) -> Option<&mut Node> {
+        self.root.find_mut(path)
+    }
+}
+
+impl Default for Scene {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,391 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use crate::math::vector3d::Vector3D;
+use crate::math::transform::Transform;
+use crate::core::color::Color;
+use crate::core::camera::Camera;
+use crate::cameras::path::{CameraPath, Target};
+use crate::objects::object::Object;
+use crate::objects::sphere::Sphere;
+use crate::objects::cube::Cube;
+use crate::objects::cylinder::Cylinder;
+use crate::objects::plane::Plane;
+use crate::objects::rectangle::Rectangle;
+use crate::materials::material::Material;
+use crate::media::fog::Fog;
+use crate::media::medium::Medium;
+use crate::renderer::renderer::{Integrator, Renderer};
+use crate::renderer::path_tracer::PathTracer;
+use super::light::Light;
+use super::node::Node;
+use super::scene::Scene;
+
+// Plain-text scene description, one statement per line and `#` starting a comment. A
+// statement is a kind followed by named fields, each a word and the numbers after it:
+//
+//     image width 800 height 600 samples 16
+//     integrator path depth 8
+//     camera position 0 5 -15 look_at 0 0 0 up 0 1 0 fov 90
+//     light position -10 10 -10 color 1 1 1 intensity 1
+//     sphere center -4 0 2 radius 1.5 color 0.9 0.2 0.2 reflectivity 0.3
+//     plane point 0 -2 0 normal 0 1 0
+//     path orbit frames 36
+//
+// Objects take `color`, `reflectivity`, `metallic`, `roughness`, `emission` with
+// `strength`, and `scale` (one factor or three), `rotate` (axis and degrees) and
+// `translate`, applied in that order.
+pub struct SceneFile {
+    pub scene: Scene,
+    pub camera: Camera,
+    pub renderer: Renderer,
+    pub camera_path: Option<CameraPath>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SceneFileError {
+    // 1-based, 0 when the error isn't about a particular line
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for SceneFileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.line > 0 {
+            write!(f, "line {}: {}", self.line, self.message)
+        } else {
+            write!(f, "{}", self.message)
+        }
+    }
+}
+
+impl std::error::Error for SceneFileError {}
+
+type Result<T> = std::result::Result<T, SceneFileError>;
+
+// One line split into its kind and fields
+struct Statement<'a> {
+    line: usize,
+    kind: &'a str,
+    fields: Vec<(&'a str, Vec<f32>)>,
+}
+
+impl<'a> Statement<'a> {
+    fn parse(line: usize, text: &'a str) -> Result<Option<Self>> {
+        let text = text.split('#').next().unwrap_or("");
+        let mut tokens = text.split_whitespace();
+        let kind = match tokens.next() {
+            Some(kind) => kind,
+            None => return Ok(None),
+        };
+
+        let mut fields: Vec<(&str, Vec<f32>)> = Vec::new();
+        for token in tokens {
+            if token.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+                fields.push((token, Vec::new()));
+                continue;
+            }
+            let value = token.parse().map_err(|_| SceneFileError { line, message: format!("'{}' is not a number", token) })?;
+            match fields.last_mut() {
+                Some((_, values)) => values.push(value),
+                None => return Err(SceneFileError { line, message: format!("number {} before any field name", token) }),
+            }
+        }
+        Ok(Some(Self { line, kind, fields }))
+    }
+
+    fn error(&self, message: String) -> SceneFileError {
+        SceneFileError { line: self.line, message }
+    }
+
+    // Rejects misspelt or misplaced fields rather than silently ignoring them
+    fn allow(&self, names: &[&str]) -> Result<()> {
+        match self.fields.iter().find(|(name, _)| !names.contains(name)) {
+            Some((name, _)) => Err(self.error(format!("unknown field '{}' for {}", name, self.kind))),
+            None => Ok(()),
+        }
+    }
+
+    fn raw(&self, name: &str) -> Option<&[f32]> {
+        self.fields.iter().find(|(field, _)| *field == name).map(|(_, values)| values.as_slice())
+    }
+
+    fn values(&self, name: &str, count: usize) -> Result<Option<&[f32]>> {
+        match self.raw(name) {
+            Some(values) if values.len() == count => Ok(Some(values)),
+            Some(values) => Err(self.error(format!("'{}' takes {} numbers, found {}", name, count, values.len()))),
+            None => Ok(None),
+        }
+    }
+
+    fn has(&self, name: &str) -> bool {
+        self.fields.iter().any(|(field, _)| *field == name)
+    }
+
+    fn number(&self, name: &str) -> Result<Option<f32>> {
+        Ok(self.values(name, 1)?.map(|v| v[0]))
+    }
+
+    fn vector(&self, name: &str) -> Result<Option<Vector3D>> {
+        Ok(self.values(name, 3)?.map(|v| Vector3D::new(v[0], v[1], v[2])))
+    }
+
+    fn color(&self, name: &str) -> Result<Option<Color>> {
+        Ok(self.values(name, 3)?.map(|v| Color::new(v[0], v[1], v[2])))
+    }
+
+    // Every occurrence of a repeated field holding a point
+    fn vectors(&self, name: &str) -> Result<Vec<Vector3D>> {
+        self.fields
+            .iter()
+            .filter(|(field, _)| *field == name)
+            .map(|(_, v)| match v.as_slice() {
+                [x, y, z] => Ok(Vector3D::new(*x, *y, *z)),
+                _ => Err(self.error(format!("'{}' takes 3 numbers, found {}", name, v.len()))),
+            })
+            .collect()
+    }
+
+    fn require<T>(&self, name: &str, value: Option<T>) -> Result<T> {
+        value.ok_or_else(|| self.error(format!("{} needs '{}'", self.kind, name)))
+    }
+}
+
+const OBJECT_FIELDS: [&str; 9] = ["color", "reflectivity", "metallic", "roughness", "emission", "strength", "scale", "rotate", "translate"];
+
+impl SceneFile {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .map_err(|error| SceneFileError { line: 0, message: format!("cannot read {}: {}", path.display(), error) })?;
+        Self::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut scene = Scene::new();
+        let mut camera = None;
+        let mut path = None;
+        let (mut width, mut height, mut samples) = (800, 600, 1);
+        let mut integrator = Integrator::Whitted;
+
+        for (index, line) in text.lines().enumerate() {
+            let statement = match Statement::parse(index + 1, line)? {
+                Some(statement) => statement,
+                None => continue,
+            };
+            match statement.kind {
+                "image" => {
+                    statement.allow(&["width", "height", "samples"])?;
+                    width = statement.number("width")?.map_or(width, |w| w as u32);
+                    height = statement.number("height")?.map_or(height, |h| h as u32);
+                    samples = statement.number("samples")?.map_or(samples, |s| s as u32);
+                }
+                "integrator" => {
+                    statement.allow(&["whitted", "path", "depth"])?;
+                    integrator = if statement.has("path") {
+                        let depth = statement.number("depth")?;
+                        Integrator::PathTracer(depth.map_or_else(PathTracer::new, |d| PathTracer::new().with_max_depth(d as u32)))
+                    } else {
+                        Integrator::Whitted
+                    };
+                }
+                "camera" => camera = Some(parse_camera(&statement)?),
+                "light" => scene.add_light(parse_light(&statement)?),
+                "fog" => {
+                    statement.allow(&["color", "density"])?;
+                    let color = statement.color("color")?.unwrap_or(Color::new(1.0, 1.0, 1.0));
+                    let density = statement.require("density", statement.number("density")?)?;
+                    scene.set_fog(Fog::new(color, density));
+                }
+                "medium" => {
+                    statement.allow(&["absorption", "scattering", "color", "anisotropy"])?;
+                    let medium = Medium::homogeneous(
+                        statement.number("absorption")?.unwrap_or(0.0),
+                        statement.number("scattering")?.unwrap_or(0.0),
+                    )
+                    .with_color(statement.color("color")?.unwrap_or(Color::new(1.0, 1.0, 1.0)))
+                    .with_anisotropy(statement.number("anisotropy")?.unwrap_or(0.0));
+                    scene.add_medium(medium);
+                }
+                "path" => path = Some(statement),
+                _ => scene.add_node(parse_object(&statement)?),
+            }
+        }
+
+        let aspect_ratio = width as f32 / height.max(1) as f32;
+        let camera = camera
+            .unwrap_or_else(|| Camera::new(Vector3D::new(0.0, 0.0, -5.0), Vector3D::new(0.0, 0.0, 0.0), Vector3D::new(0.0, 1.0, 0.0), 90.0, 1.0))
+            .with_aspect_ratio(aspect_ratio);
+        let camera_path = path.map(|statement| parse_path(&statement, &camera)).transpose()?;
+        let renderer = Renderer::new(width, height).with_integrator(integrator).with_samples(samples);
+        Ok(Self { scene, camera, renderer, camera_path })
+    }
+}
+
+// A camera path written as in a scene file without the leading `path`, e.g.
+// "orbit frames 36", placed relative to `camera`
+pub fn parse_camera_path(text: &str, camera: &Camera) -> Result<CameraPath> {
+    let line = format!("path {}", text);
+    let statement = Statement::parse(0, &line)?.expect("Line starts with 'path'");
+    parse_path(&statement, camera)
+}
+
+fn parse_camera(statement: &Statement) -> Result<Camera> {
+    statement.allow(&[
+        "position", "look_at", "up", "fov", "focal_length", "sensor", "shift", "tilt",
+        "aperture", "f_stop", "focus", "blades", "rotation", "shutter",
+    ])?;
+    let mut camera = Camera::new(
+        statement.vector("position")?.unwrap_or(Vector3D::new(0.0, 0.0, -5.0)),
+        statement.vector("look_at")?.unwrap_or(Vector3D::new(0.0, 0.0, 0.0)),
+        statement.vector("up")?.unwrap_or(Vector3D::new(0.0, 1.0, 0.0)),
+        statement.number("fov")?.unwrap_or(90.0),
+        1.0,
+    );
+    if let Some(sensor) = statement.values("sensor", 2)? {
+        camera = camera.with_sensor(sensor[0], sensor[1]);
+    }
+    if let Some(focal_length) = statement.number("focal_length")? {
+        camera = camera.with_focal_length(focal_length);
+    }
+    if let Some(shift) = statement.values("shift", 2)? {
+        camera = camera.with_shift(shift[0], shift[1]);
+    }
+    if let Some(tilt) = statement.values("tilt", 2)? {
+        camera = camera.with_tilt(tilt[0], tilt[1]);
+    }
+    if let Some(radius) = statement.number("aperture")? {
+        camera = camera.with_aperture(radius);
+    }
+    if let Some(f_stop) = statement.number("f_stop")? {
+        camera = camera.with_f_stop(f_stop);
+    }
+    if let Some(distance) = statement.number("focus")? {
+        camera = camera.with_focus_distance(distance);
+    }
+    if let Some(blades) = statement.number("blades")? {
+        camera = camera.with_blades(blades as u32, statement.number("rotation")?.unwrap_or(0.0));
+    }
+    if let Some(shutter) = statement.values("shutter", 2)? {
+        camera = camera.with_shutter(shutter[0], shutter[1]);
+    }
+    Ok(camera)
+}
+
+fn parse_light(statement: &Statement) -> Result<Light> {
+    statement.allow(&["position", "color", "intensity", "direction", "inner", "outer"])?;
+    let position = statement.require("position", statement.vector("position")?)?;
+    let color = statement.color("color")?.unwrap_or(Color::new(1.0, 1.0, 1.0));
+    let intensity = statement.number("intensity")?.unwrap_or(1.0);
+    Ok(match statement.vector("direction")? {
+        Some(direction) => {
+            let outer = statement.number("outer")?.unwrap_or(30.0);
+            let inner = statement.number("inner")?.unwrap_or(outer);
+            Light::spot(position, direction, inner, outer, color, intensity)
+        }
+        None => Light::new(position, color, intensity),
+    })
+}
+
+fn parse_object(statement: &Statement) -> Result<Node> {
+    let geometry: Arc<dyn Object> = match statement.kind {
+        "sphere" => {
+            statement.allow(&[&["center", "radius"][..], &OBJECT_FIELDS].concat())?;
+            let center = statement.vector("center")?.unwrap_or(Vector3D::new(0.0, 0.0, 0.0));
+            Arc::new(Sphere::new(center, statement.require("radius", statement.number("radius")?)?))
+        }
+        "cube" => {
+            statement.allow(&[&["min", "max"][..], &OBJECT_FIELDS].concat())?;
+            let min = statement.require("min", statement.vector("min")?)?;
+            Arc::new(Cube::new(min, statement.require("max", statement.vector("max")?)?))
+        }
+        "cylinder" => {
+            statement.allow(&[&["center", "axis", "radius", "height"][..], &OBJECT_FIELDS].concat())?;
+            Arc::new(Cylinder::new(
+                statement.vector("center")?.unwrap_or(Vector3D::new(0.0, 0.0, 0.0)),
+                statement.vector("axis")?.unwrap_or(Vector3D::new(0.0, 1.0, 0.0)),
+                statement.require("radius", statement.number("radius")?)?,
+                statement.require("height", statement.number("height")?)?,
+            ))
+        }
+        "plane" => {
+            statement.allow(&[&["point", "normal"][..], &OBJECT_FIELDS].concat())?;
+            Arc::new(Plane::new(
+                statement.vector("point")?.unwrap_or(Vector3D::new(0.0, 0.0, 0.0)),
+                statement.vector("normal")?.unwrap_or(Vector3D::new(0.0, 1.0, 0.0)),
+            ))
+        }
+        "rectangle" => {
+            statement.allow(&[&["center", "normal", "width", "height"][..], &OBJECT_FIELDS].concat())?;
+            Arc::new(Rectangle::centered(
+                statement.vector("center")?.unwrap_or(Vector3D::new(0.0, 0.0, 0.0)),
+                statement.require("normal", statement.vector("normal")?)?,
+                statement.require("width", statement.number("width")?)?,
+                statement.require("height", statement.number("height")?)?,
+            ))
+        }
+        kind => return Err(statement.error(format!("unknown statement '{}'", kind))),
+    };
+
+    let mut material = Material::new(statement.color("color")?.unwrap_or(Color::new(1.0, 1.0, 1.0)));
+    if let Some(reflectivity) = statement.number("reflectivity")? {
+        material = material.with_reflectivity(reflectivity);
+    }
+    if let Some(metallic) = statement.number("metallic")? {
+        material = material.with_metallic(metallic);
+    }
+    if let Some(roughness) = statement.number("roughness")? {
+        material = material.with_roughness(roughness);
+    }
+    if let Some(emission) = statement.color("emission")? {
+        material = material.with_emission(emission, statement.number("strength")?.unwrap_or(1.0));
+    }
+
+    let mut transform = Transform::identity();
+    match statement.raw("scale") {
+        Some(&[factor]) => transform = transform.then(&Transform::uniform_scale(factor)),
+        Some(&[x, y, z]) => transform = transform.then(&Transform::scale(Vector3D::new(x, y, z))),
+        Some(_) => return Err(statement.error("'scale' takes 1 or 3 numbers".to_string())),
+        None => {}
+    }
+    if let Some(rotate) = statement.values("rotate", 4)? {
+        transform = transform.then(&Transform::rotate(Vector3D::new(rotate[0], rotate[1], rotate[2]), rotate[3]));
+    }
+    if let Some(offset) = statement.vector("translate")? {
+        transform = transform.then(&Transform::translate(offset));
+    }
+
+    Ok(Node::geometry(statement.kind, geometry)
+        .with_transform(transform)
+        .with_material(Arc::new(material)))
+}
+
+fn parse_path(statement: &Statement, camera: &Camera) -> Result<CameraPath> {
+    let kind = statement.fields.first().map(|(name, _)| *name).unwrap_or("");
+    let fields: &[&str] = match kind {
+        "orbit" => &["orbit", "frames"],
+        "dolly" => &["dolly", "frames", "to"],
+        "spline" => &["spline", "frames", "point", "target", "forward"],
+        _ => return Err(statement.error("path must be 'orbit', 'dolly' or 'spline'".to_string())),
+    };
+    statement.allow(fields)?;
+    let frames = statement.require("frames", statement.number("frames")?)? as u32;
+    match kind {
+        "orbit" => Ok(CameraPath::orbit(camera, frames)),
+        "dolly" => Ok(CameraPath::dolly(camera, statement.require("to", statement.vector("to")?)?, frames)),
+        _ => {
+            let waypoints = statement.vectors("point")?;
+            if waypoints.len() < 2 {
+                return Err(statement.error("a spline path needs at least 2 'point's".to_string()));
+            }
+            let targets = statement.vectors("target")?;
+            let target = match targets.len() {
+                _ if statement.has("forward") => Target::Forward,
+                0 => Target::Fixed(camera.look_at()),
+                1 => Target::Fixed(targets[0]),
+                _ => Target::Spline(targets),
+            };
+            Ok(CameraPath::spline(waypoints, target, frames))
+        }
+    }
+}
@@ -0,0 +1,262 @@
+use crate::math::vector3d::Vector3D;
+use crate::core::color::Color;
+use super::texture::{Footprint, Texture};
+
+// How texture coordinates outside [0, 1] are brought back onto the image
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum WrapMode {
+    Repeat,
+    MirroredRepeat,
+    Clamp,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FilterMode {
+    Nearest,
+    Bilinear,
+    // Bilinear lookups in the two mip levels bracketing the footprint size, blended
+    Trilinear,
+    // Elliptical weighted average over the footprint, sharp along receding surfaces
+    Ewa,
+}
+
+// Longest-to-shortest axis ratio allowed for EWA footprints, bounding the texel count
+const MAX_ANISOTROPY: f32 = 8.0;
+
+struct MipLevel {
+    width: u32,
+    height: u32,
+    pixels: Vec<Color>,
+}
+
+impl MipLevel {
+    // Box-filters 2x2 blocks; odd edges reuse their last row or column
+    fn downsample(&self) -> MipLevel {
+        let width = (self.width / 2).max(1);
+        let height = (self.height / 2).max(1);
+        let at = |x: u32, y: u32| self.pixels[(y.min(self.height - 1) * self.width + x.min(self.width - 1)) as usize];
+
+        let mut pixels = Vec::with_capacity((width * height) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let (x2, y2) = (x * 2, y * 2);
+                pixels.push((at(x2, y2) + at(x2 + 1, y2) + at(x2, y2 + 1) + at(x2 + 1, y2 + 1)) * 0.25);
+            }
+        }
+        MipLevel { width, height, pixels }
+    }
+}
+
+pub struct ImageTexture {
+    pub width: u32,
+    pub height: u32,
+    pub wrap: WrapMode,
+    pub filter: FilterMode,
+    levels: Vec<MipLevel>,
+}
+
+impl ImageTexture {
+    pub fn new(width: u32, height: u32, pixels: Vec<Color>) -> Self {
+        assert_eq!(pixels.len(), (width * height) as usize, "pixel count does not match the image size");
+
+        let mut levels = vec![MipLevel { width, height, pixels }];
+        while let Some(last) = levels.last().filter(|level| level.width > 1 || level.height > 1) {
+            let next = last.downsample();
+            levels.push(next);
+        }
+
+        Self { width, height, wrap: WrapMode::Repeat, filter: FilterMode::Trilinear, levels }
+    }
+
+    pub fn open(path: &str) -> Result<Self, image::ImageError> {
+        let image = image::open(path)?.to_rgb8();
+        let pixels = image
+            .pixels()
+            .map(|p| Color::new(p[0] as f32 / 255.0, p[1] as f32 / 255.0, p[2] as f32 / 255.0))
+            .collect();
+        Ok(Self::new(image.width(), image.height(), pixels))
+    }
+
+    pub fn with_wrap(mut self, wrap: WrapMode) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    pub fn with_filter(mut self, filter: FilterMode) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn mip_levels(&self) -> usize {
+        self.levels.len()
+    }
+
+    fn wrap_index(&self, i: i64, size: u32) -> u32 {
+        let size = size as i64;
+        let wrapped = match self.wrap {
+            WrapMode::Repeat => i.rem_euclid(size),
+            WrapMode::MirroredRepeat => {
+                let period = i.rem_euclid(2 * size);
+                if period < size { period } else { 2 * size - 1 - period }
+            }
+            WrapMode::Clamp => i.clamp(0, size - 1),
+        };
+        wrapped as u32
+    }
+
+    fn level_texel(&self, level: usize, x: i64, y: i64) -> Color {
+        let level = &self.levels[level];
+        let x = self.wrap_index(x, level.width);
+        let y = self.wrap_index(y, level.height);
+        level.pixels[(y * level.width + x) as usize]
+    }
+
+    // Texel with wrapping applied; row 0 is the top of the image
+    pub fn texel(&self, x: i64, y: i64) -> Color {
+        self.level_texel(0, x, y)
+    }
+
+    fn bilinear(&self, level: usize, u: f32, v: f32) -> Color {
+        let size = &self.levels[level];
+        // v grows upwards while image rows grow downwards
+        let x = u * size.width as f32 - 0.5;
+        let y = (1.0 - v) * size.height as f32 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+        self.level_texel(level, x0, y0) * ((1.0 - fx) * (1.0 - fy))
+            + self.level_texel(level, x0 + 1, y0) * (fx * (1.0 - fy))
+            + self.level_texel(level, x0, y0 + 1) * ((1.0 - fx) * fy)
+            + self.level_texel(level, x0 + 1, y0 + 1) * (fx * fy)
+    }
+
+    // Unfiltered lookup at full resolution
+    pub fn sample(&self, u: f32, v: f32) -> Color {
+        match self.filter {
+            FilterMode::Nearest => {
+                let x = u * self.width as f32;
+                let y = (1.0 - v) * self.height as f32;
+                self.texel(x.floor() as i64, y.floor() as i64)
+            }
+            _ => self.bilinear(0, u, v),
+        }
+    }
+
+    // Continuous mip level whose texels are `width` (in texture coordinates) across
+    fn level_of_detail(&self, width: f32) -> f32 {
+        let finest = self.width.max(self.height) as f32;
+        (width.max(1e-8) * finest).log2().clamp(0.0, (self.levels.len() - 1) as f32)
+    }
+
+    fn trilinear(&self, u: f32, v: f32, footprint: &Footprint) -> Color {
+        let width = 2.0 * footprint.dudx.abs()
+            .max(footprint.dvdx.abs())
+            .max(footprint.dudy.abs())
+            .max(footprint.dvdy.abs());
+        let level = self.level_of_detail(width);
+        let fine = level.floor() as usize;
+        if fine + 1 >= self.levels.len() {
+            return self.bilinear(fine, u, v);
+        }
+        let blend = level - fine as f32;
+        self.bilinear(fine, u, v) * (1.0 - blend) + self.bilinear(fine + 1, u, v) * blend
+    }
+
+    fn ewa(&self, u: f32, v: f32, footprint: &Footprint) -> Color {
+        // Image space runs downwards in v
+        let mut major = (footprint.dudx, -footprint.dvdx);
+        let mut minor = (footprint.dudy, -footprint.dvdy);
+        let length = |axis: (f32, f32)| (axis.0 * axis.0 + axis.1 * axis.1).sqrt();
+        if length(major) < length(minor) {
+            std::mem::swap(&mut major, &mut minor);
+        }
+
+        let major_length = length(major);
+        let mut minor_length = length(minor);
+        if minor_length == 0.0 {
+            return self.bilinear(0, u, v);
+        }
+
+        // Clamp eccentricity by widening the minor axis
+        if minor_length * MAX_ANISOTROPY < major_length {
+            let scale = major_length / (minor_length * MAX_ANISOTROPY);
+            minor = (minor.0 * scale, minor.1 * scale);
+            minor_length *= scale;
+        }
+
+        let level = self.level_of_detail(minor_length);
+        let fine = level.floor() as usize;
+        // Footprints wider than the whole image just see its average
+        if fine + 1 >= self.levels.len() {
+            return self.bilinear(fine, u, v);
+        }
+        let blend = level - fine as f32;
+        self.ewa_level(fine, u, v, major, minor) * (1.0 - blend)
+            + self.ewa_level(fine + 1, u, v, major, minor) * blend
+    }
+
+    fn ewa_level(&self, level: usize, u: f32, v: f32, axis0: (f32, f32), axis1: (f32, f32)) -> Color {
+        let size = &self.levels[level];
+        let (w, h) = (size.width as f32, size.height as f32);
+        let s = u * w - 0.5;
+        let t = (1.0 - v) * h - 0.5;
+        let (ds0, dt0) = (axis0.0 * w, axis0.1 * h);
+        let (ds1, dt1) = (axis1.0 * w, axis1.1 * h);
+
+        // Implicit ellipse a*s^2 + b*s*t + c*t^2 = 1, grown by a texel so it never
+        // falls between samples
+        let mut a = dt0 * dt0 + dt1 * dt1 + 1.0;
+        let mut b = -2.0 * (ds0 * dt0 + ds1 * dt1);
+        let mut c = ds0 * ds0 + ds1 * ds1 + 1.0;
+        let inv_f = 1.0 / (a * c - b * b * 0.25);
+        a *= inv_f;
+        b *= inv_f;
+        c *= inv_f;
+
+        let determinant = -b * b + 4.0 * a * c;
+        let inv_determinant = 1.0 / determinant;
+        let u_sqrt = (determinant * c).sqrt();
+        let v_sqrt = (a * determinant).sqrt();
+        let s0 = (s - 2.0 * inv_determinant * u_sqrt).ceil() as i64;
+        let s1 = (s + 2.0 * inv_determinant * u_sqrt).floor() as i64;
+        let t0 = (t - 2.0 * inv_determinant * v_sqrt).ceil() as i64;
+        let t1 = (t + 2.0 * inv_determinant * v_sqrt).floor() as i64;
+
+        let alpha: f32 = 2.0;
+        let edge = (-alpha).exp();
+        let mut sum = Color::new(0.0, 0.0, 0.0);
+        let mut weights = 0.0;
+        for it in t0..=t1 {
+            let tt = it as f32 - t;
+            for is in s0..=s1 {
+                let ss = is as f32 - s;
+                let r2 = a * ss * ss + b * ss * tt + c * tt * tt;
+                if r2 < 1.0 {
+                    let weight = (-alpha * r2).exp() - edge;
+                    sum = sum + self.level_texel(level, is, it) * weight;
+                    weights += weight;
+                }
+            }
+        }
+
+        if weights > 0.0 {
+            sum * (1.0 / weights)
+        } else {
+            self.bilinear(level, u, v)
+        }
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f32, v: f32, _point: &Vector3D) -> Color {
+        self.sample(u, v)
+    }
+
+    fn filtered_value(&self, u: f32, v: f32, _point: &Vector3D, footprint: &Footprint) -> Color {
+        match self.filter {
+            FilterMode::Nearest | FilterMode::Bilinear => self.sample(u, v),
+            FilterMode::Trilinear => self.trilinear(u, v, footprint),
+            FilterMode::Ewa => self.ewa(u, v, footprint),
+        }
+    }
+}
@@ -0,0 +1,4 @@
+pub mod texture;
+pub mod image_texture;
+pub mod noise;
+pub mod procedural;
@@ -0,0 +1,190 @@
+use std::sync::Arc;
+use crate::math::vector3d::Vector3D;
+use crate::core::color::Color;
+use super::texture::{Footprint, SolidColor, Texture};
+use super::noise::Perlin;
+
+fn mix(a: Color, b: Color, t: f32) -> Color {
+    a * (1.0 - t) + b * t
+}
+
+// Solid 3D checkerboard evaluated at the hit point; `scale` is the size of one square.
+// The two colours are textures themselves, so checkers can be nested or noisy.
+pub struct Checkerboard {
+    pub even: Arc<dyn Texture>,
+    pub odd: Arc<dyn Texture>,
+    pub scale: f32,
+}
+
+impl Checkerboard {
+    pub fn new(even: Color, odd: Color, scale: f32) -> Self {
+        Self::textured(Arc::new(SolidColor::new(even)), Arc::new(SolidColor::new(odd)), scale)
+    }
+
+    pub fn textured(even: Arc<dyn Texture>, odd: Arc<dyn Texture>, scale: f32) -> Self {
+        Self { even, odd, scale }
+    }
+}
+
+impl Checkerboard {
+    fn select(&self, point: &Vector3D) -> &Arc<dyn Texture> {
+        // Small offset so a floor lying exactly on a cell boundary doesn't flicker
+        let p = *point * (1.0 / self.scale) + Vector3D::new(1e-4, 1e-4, 1e-4);
+        let parity = p.x.floor() as i64 + p.y.floor() as i64 + p.z.floor() as i64;
+        if parity.rem_euclid(2) == 0 {
+            &self.even
+        } else {
+            &self.odd
+        }
+    }
+}
+
+impl Texture for Checkerboard {
+    fn value(&self, u: f32, v: f32, point: &Vector3D) -> Color {
+        self.select(point).value(u, v, point)
+    }
+
+    fn filtered_value(&self, u: f32, v: f32, point: &Vector3D, footprint: &Footprint) -> Color {
+        self.select(point).filtered_value(u, v, point, footprint)
+    }
+}
+
+// Grey-scale fractal noise blending between two colours
+pub struct NoiseTexture {
+    pub low: Color,
+    pub high: Color,
+    pub scale: f32,
+    pub octaves: u32,
+    pub turbulent: bool,
+    perlin: Perlin,
+}
+
+impl NoiseTexture {
+    pub fn new(low: Color, high: Color, scale: f32) -> Self {
+        Self { low, high, scale, octaves: 1, turbulent: false, perlin: Perlin::default() }
+    }
+
+    pub fn fbm(mut self, octaves: u32) -> Self {
+        self.octaves = octaves.max(1);
+        self
+    }
+
+    pub fn turbulence(mut self, octaves: u32) -> Self {
+        self.octaves = octaves.max(1);
+        self.turbulent = true;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.perlin = Perlin::new(seed);
+        self
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn value(&self, _u: f32, _v: f32, point: &Vector3D) -> Color {
+        let p = *point * self.scale;
+        let t = if self.turbulent {
+            self.perlin.turbulence(&p, self.octaves)
+        } else {
+            0.5 + 0.5 * self.perlin.fbm(&p, self.octaves)
+        };
+        mix(self.low, self.high, t.clamp(0.0, 1.0))
+    }
+}
+
+// Veins from a sine wave along x, distorted by turbulence
+pub struct Marble {
+    pub base: Color,
+    pub vein: Color,
+    pub scale: f32,
+    pub distortion: f32,
+    perlin: Perlin,
+}
+
+impl Marble {
+    pub fn new(base: Color, vein: Color, scale: f32) -> Self {
+        Self { base, vein, scale, distortion: 5.0, perlin: Perlin::default() }
+    }
+
+    pub fn with_distortion(mut self, distortion: f32) -> Self {
+        self.distortion = distortion;
+        self
+    }
+}
+
+impl Texture for Marble {
+    fn value(&self, _u: f32, _v: f32, point: &Vector3D) -> Color {
+        let p = *point * self.scale;
+        let wave = (p.x + self.distortion * self.perlin.turbulence(&p, 6)).sin();
+        let t = (1.0 - wave.abs()).powi(4);
+        mix(self.base, self.vein, t)
+    }
+}
+
+// Concentric growth rings around the y axis through `center`, wobbled by noise
+pub struct Wood {
+    pub light: Color,
+    pub dark: Color,
+    pub center: Vector3D,
+    pub ring_spacing: f32,
+    pub wobble: f32,
+    perlin: Perlin,
+}
+
+impl Wood {
+    pub fn new(light: Color, dark: Color, ring_spacing: f32) -> Self {
+        Self {
+            light,
+            dark,
+            center: Vector3D::new(0.0, 0.0, 0.0),
+            ring_spacing,
+            wobble: 0.3,
+            perlin: Perlin::default(),
+        }
+    }
+
+    pub fn with_center(mut self, center: Vector3D) -> Self {
+        self.center = center;
+        self
+    }
+}
+
+impl Texture for Wood {
+    fn value(&self, _u: f32, _v: f32, point: &Vector3D) -> Color {
+        let d = *point - self.center;
+        let radius = (d.x * d.x + d.z * d.z).sqrt() / self.ring_spacing;
+        let rings = radius + self.wobble * self.perlin.fbm(&(d * (1.0 / self.ring_spacing)), 3);
+        let t = (rings - rings.floor()).powf(0.5);
+        mix(self.dark, self.light, t)
+    }
+}
+
+// Solid 3D grid evaluated at the hit point: sheets every `spacing` units across each
+// axis, which any surface shows as lines. The sheets sit halfway between multiples of
+// `spacing`, so a floor or wall lying on a whole multiple, the usual case, is crossed by
+// lines rather than filled by one.
+pub struct Grid {
+    pub background: Color,
+    pub line: Color,
+    pub spacing: f32,
+    pub line_width: f32,
+}
+
+impl Grid {
+    pub fn new(background: Color, line: Color, spacing: f32, line_width: f32) -> Self {
+        Self { background, line, spacing, line_width }
+    }
+}
+
+impl Texture for Grid {
+    fn value(&self, _u: f32, _v: f32, point: &Vector3D) -> Color {
+        let half_width = 0.5 * self.line_width / self.spacing;
+        let near_line = |c: f32| ((c / self.spacing).rem_euclid(1.0) - 0.5).abs() < half_width;
+        if near_line(point.x) || near_line(point.y) || near_line(point.z) {
+            self.line
+        } else {
+            self.background
+        }
+    }
+}
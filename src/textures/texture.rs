@@ -0,0 +1,40 @@
+use crate::math::vector3d::Vector3D;
+use crate::core::color::Color;
+
+// Screen-space derivatives of the texture coordinates: how far u and v move across one
+// pixel in x and in y.
+#[derive(Debug, Copy, Clone)]
+pub struct Footprint {
+    pub dudx: f32,
+    pub dvdx: f32,
+    pub dudy: f32,
+    pub dvdy: f32,
+}
+
+// A colour that varies over a surface, looked up by texture coordinates and/or the
+// world-space hit point.
+pub trait Texture: Send + Sync {
+    fn value(&self, u: f32, v: f32, point: &Vector3D) -> Color;
+
+    // Lookup averaged over the pixel footprint. Textures that can't prefilter just
+    // return the point sample.
+    fn filtered_value(&self, u: f32, v: f32, point: &Vector3D, _footprint: &Footprint) -> Color {
+        self.value(u, v, point)
+    }
+}
+
+pub struct SolidColor {
+    pub color: Color,
+}
+
+impl SolidColor {
+    pub fn new(color: Color) -> Self {
+        Self { color }
+    }
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _u: f32, _v: f32, _point: &Vector3D) -> Color {
+        self.color
+    }
+}